@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::AbortSignal;
+use crate::{Mutex, MutexRef, LockFuture, AbortError, Pointer};
+
+impl <T: ?Sized> Mutex<T> {
+    /// Waits for the lock unless `signal` aborts first, in which case the
+    /// place in the queue is given up. A signal that has already aborted
+    /// fails without taking the lock, even if it is free.
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn lock_with_signal(&self, signal: &AbortSignal) -> LockWithSignal<'_, T> {
+        LockWithSignal {
+            lock: self.lock(),
+            signal: signal.clone(),
+            listener: None,
+        }
+    }
+}
+
+pub struct LockWithSignal<'a, T: ?Sized> {
+    lock: LockFuture<'a, T>,
+    signal: AbortSignal,
+    // added once the future has to wait
+    listener: Option<AbortListener>,
+}
+
+// Wakes the waiting task when the signal's `abort` event fires.
+struct AbortListener {
+    signal: AbortSignal,
+    waker: Pointer<RefCell<Option<Waker>>>,
+    callback: Closure<dyn FnMut()>,
+}
+
+impl AbortListener {
+    fn new(signal: &AbortSignal) -> Self {
+        let waker: Pointer<RefCell<Option<Waker>>> = Default::default();
+        let callback = Closure::<dyn FnMut()>::new({
+            let waker = waker.clone();
+            move || {
+                if let Some(waker) = waker.borrow_mut().take() {
+                    waker.wake();
+                }
+            }
+        });
+        // adding a listener only fails for a callback that is not a function
+        let _ = signal.add_event_listener_with_callback("abort", callback.as_ref().unchecked_ref());
+        AbortListener { signal: signal.clone(), waker, callback }
+    }
+}
+
+impl Drop for AbortListener {
+    fn drop(&mut self) {
+        let _ = self.signal.remove_event_listener_with_callback("abort", self.callback.as_ref().unchecked_ref());
+    }
+}
+
+impl <'a, T: ?Sized> Future for LockWithSignal<'a, T> {
+    type Output = Result<MutexRef<'a, T>, AbortError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.signal.aborted() {
+            return Poll::Ready(Err(AbortError(())));
+        }
+        if let Poll::Ready(guard) = Pin::new(&mut this.lock).poll(cx) {
+            return Poll::Ready(Ok(guard));
+        }
+        let listener = this.listener.get_or_insert_with(|| AbortListener::new(&this.signal));
+        *listener.waker.borrow_mut() = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
@@ -0,0 +1,90 @@
+use core::cell::RefCell;
+use core::task::{Waker, Poll};
+use core::future::poll_fn;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug)]
+struct BarrierState {
+    size: usize,
+    arrived: usize,
+    generation: usize,
+    waiters: Waiters,
+}
+
+#[derive(Debug, Clone)]
+pub struct Barrier {
+    state: Pointer<RefCell<BarrierState>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// A barrier for zero tasks behaves like a barrier for one.
+    pub fn new(n: usize) -> Self {
+        Barrier {
+            state: Pointer::new(RefCell::new(BarrierState {
+                size: n.max(1),
+                arrived: 0,
+                generation: 0,
+                waiters: Default::default(),
+            })),
+        }
+    }
+
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let (generation, waker_id) = {
+            let mut state = (*self.state).borrow_mut();
+            state.arrived += 1;
+            if state.arrived < state.size {
+                (state.generation, state.waiters.next_id())
+            } else {
+                state.arrived = 0;
+                state.generation = state.generation.wrapping_add(1);
+                let wakers: Vec<_> = state.waiters.drain().collect();
+                drop(state);
+                wakers.into_iter().for_each(Waker::wake);
+                return BarrierWaitResult(true);
+            }
+        };
+
+        let arrival = Arrival { barrier: self, generation, waker_id };
+        poll_fn(|cx| {
+            let mut state = (*self.state).borrow_mut();
+            if state.generation != generation {
+                Poll::Ready(())
+            } else {
+                state.waiters.register(waker_id, cx.waker(), ());
+                Poll::Pending
+            }
+        }).await;
+        drop(arrival);
+        BarrierWaitResult(false)
+    }
+}
+
+// Frees the waiter's slot and, if the wait was cancelled before the barrier
+// released it, takes back its arrival.
+struct Arrival<'a> {
+    barrier: &'a Barrier,
+    generation: usize,
+    waker_id: WakerId,
+}
+
+impl <'a> Drop for Arrival<'a> {
+    fn drop(&mut self) {
+        let mut state = (*self.barrier.state).borrow_mut();
+        if state.generation == self.generation {
+            state.arrived -= 1;
+        }
+        state.waiters.cancel(self.waker_id);
+    }
+}
@@ -0,0 +1,294 @@
+use core::cell::RefCell;
+use alloc::collections::VecDeque;
+use core::error::Error;
+use core::fmt;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+/// What a send does once the buffer holds `capacity` messages that some
+/// receiver has not seen yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Drop the oldest message; receivers that missed it see `Lagged`.
+    #[default]
+    DropOldest,
+    /// Refuse the new message with `SendError::Full`.
+    RejectNew,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    value: T,
+    // receivers that have yet to see this message
+    remaining: usize,
+}
+
+#[derive(Debug)]
+struct BroadcastState<T> {
+    buffer: VecDeque<Slot<T>>,
+    // sequence number of the front of the buffer
+    head: u64,
+    capacity: usize,
+    overflow: Overflow,
+    senders: usize,
+    receivers: usize,
+    waiters: Waiters,
+}
+
+impl <T> BroadcastState<T> {
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+
+    fn trim(&mut self) {
+        while self.buffer.front().is_some_and(|slot| slot.remaining == 0) {
+            self.buffer.pop_front();
+            self.head += 1;
+        }
+    }
+
+    fn wake(&mut self) -> Vec<Waker> {
+        self.waiters.drain().collect()
+    }
+}
+
+type Shared<T> = Pointer<RefCell<BroadcastState<T>>>;
+
+fn wake_all(wakers: Vec<Waker>) {
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError<T> {
+    /// There are no receivers left to see the message.
+    Closed(T),
+    Full(T),
+}
+
+impl <T> SendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            SendError::Closed(value) | SendError::Full(value) => value,
+        }
+    }
+}
+
+impl <T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Closed(_) => write!(f, "channel closed"),
+            SendError::Full(_) => write!(f, "channel full"),
+        }
+    }
+}
+
+impl <T: fmt::Debug> Error for SendError<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    Closed,
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Closed => write!(f, "channel closed"),
+            RecvError::Lagged(skipped) => write!(f, "channel lagged by {}", skipped),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Closed,
+    Lagged(u64),
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "channel empty"),
+            TryRecvError::Closed => write!(f, "channel closed"),
+            TryRecvError::Lagged(skipped) => write!(f, "channel lagged by {}", skipped),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    channel_with(capacity, Overflow::default())
+}
+
+// A zero capacity panics, or is taken as one with `panic_free`.
+pub fn channel_with<T: Clone>(capacity: usize, overflow: Overflow) -> (Sender<T>, Receiver<T>) {
+    #[cfg(not(feature = "panic_free"))]
+    assert!(capacity > 0, "broadcast channel requires capacity > 0");
+    #[cfg(feature = "panic_free")]
+    let capacity = capacity.max(1);
+    let shared = Pointer::new(RefCell::new(BroadcastState {
+        buffer: VecDeque::with_capacity(capacity),
+        head: 0,
+        capacity,
+        overflow,
+        senders: 1,
+        receivers: 1,
+        waiters: Default::default(),
+    }));
+    (Sender { shared: shared.clone() }, Receiver { shared, next: 0 })
+}
+
+#[derive(Debug)]
+pub struct Sender<T> {
+    shared: Shared<T>,
+}
+
+impl <T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        (*self.shared).borrow_mut().senders += 1;
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl <T: Clone> Sender<T> {
+    /// Returns the number of receivers the message was sent to.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let (receivers, wakers) = {
+            let mut state = (*self.shared).borrow_mut();
+            if state.receivers == 0 {
+                return Err(SendError::Closed(value));
+            }
+            state.trim();
+            if state.buffer.len() == state.capacity {
+                match state.overflow {
+                    Overflow::DropOldest => {
+                        state.buffer.pop_front();
+                        state.head += 1;
+                    }
+                    Overflow::RejectNew => return Err(SendError::Full(value)),
+                }
+            }
+            let remaining = state.receivers;
+            state.buffer.push_back(Slot { value, remaining });
+            (remaining, state.wake())
+        };
+        wake_all(wakers);
+        Ok(receivers)
+    }
+
+    pub fn subscribe(&self) -> Receiver<T> {
+        subscribe(&self.shared)
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        (*self.shared).borrow().receivers
+    }
+}
+
+impl <T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = (*self.shared).borrow_mut();
+            state.senders -= 1;
+            if state.senders == 0 {
+                state.wake()
+            } else {
+                Vec::new()
+            }
+        };
+        wake_all(wakers);
+    }
+}
+
+// New receivers only see messages sent after they subscribed.
+fn subscribe<T>(shared: &Shared<T>) -> Receiver<T> {
+    let mut state = (**shared).borrow_mut();
+    state.receivers += 1;
+    Receiver { shared: shared.clone(), next: state.tail() }
+}
+
+#[derive(Debug)]
+pub struct Receiver<T> {
+    shared: Shared<T>,
+    next: u64,
+}
+
+impl <T: Clone> Receiver<T> {
+    pub fn recv(&mut self) -> RecvFuture<'_, T> {
+        let waker_id = (*self.shared).borrow_mut().waiters.next_id();
+        RecvFuture { waker_id, receiver: self }
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut state = (*self.shared).borrow_mut();
+        if self.next < state.head {
+            let skipped = state.head - self.next;
+            self.next = state.head;
+            return Err(TryRecvError::Lagged(skipped));
+        }
+        if self.next == state.tail() {
+            return Err(if state.senders == 0 { TryRecvError::Closed } else { TryRecvError::Empty });
+        }
+
+        let index = (self.next - state.head) as usize;
+        // `head..tail` is always buffered, so this is never empty
+        let Some(slot) = state.buffer.get_mut(index) else { return Err(TryRecvError::Empty) };
+        slot.remaining -= 1;
+        let value = slot.value.clone();
+        self.next += 1;
+        state.trim();
+        Ok(value)
+    }
+
+    pub fn resubscribe(&self) -> Self {
+        subscribe(&self.shared)
+    }
+}
+
+impl <T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = (*self.shared).borrow_mut();
+        state.receivers -= 1;
+        let start = self.next.saturating_sub(state.head) as usize;
+        for slot in state.buffer.iter_mut().skip(start) {
+            slot.remaining -= 1;
+        }
+        state.trim();
+    }
+}
+
+pub struct RecvFuture<'a, T> {
+    waker_id: WakerId,
+    receiver: &'a mut Receiver<T>,
+}
+
+impl <'a, T: Clone> Future for RecvFuture<'a, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let waker_id = self.waker_id;
+        match self.receiver.try_recv() {
+            Ok(value) => Poll::Ready(Ok(value)),
+            Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError::Closed)),
+            Err(TryRecvError::Lagged(skipped)) => Poll::Ready(Err(RecvError::Lagged(skipped))),
+            Err(TryRecvError::Empty) => {
+                (*self.receiver.shared).borrow_mut().waiters.register(waker_id, cx.waker(), ());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl <'a, T> Drop for RecvFuture<'a, T> {
+    fn drop(&mut self) {
+        (*self.receiver.shared).borrow_mut().waiters.cancel(self.waker_id);
+    }
+}
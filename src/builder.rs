@@ -0,0 +1,121 @@
+use crate::Mutex;
+#[cfg(any(feature = "watchdog", feature = "web"))]
+use std::time::Duration;
+#[cfg(feature = "watchdog")]
+use crate::LongHold;
+
+/// Who gets the lock when it is released while tasks are waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fairness {
+    /// Hand the lock to the longest-waiting task.
+    #[default]
+    Fifo,
+    /// Hand the lock to the most recently queued task.
+    Lifo,
+    /// Wake the longest-waiting task, but let any task that asks first take
+    /// the lock before it runs.
+    Unfair,
+}
+
+impl Fairness {
+    // whether a released lock is passed straight to the woken waiter
+    pub(crate) fn hands_off(self) -> bool {
+        self != Fairness::Unfair
+    }
+}
+
+/// How many waiters a release wakes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WakeStrategy {
+    /// Wake one waiter, chosen by the `Fairness` policy.
+    #[default]
+    One,
+    /// Wake every waiter and let them race for the lock; the losers queue up
+    /// again. Priorities and the fairness policy are ignored.
+    All,
+}
+
+/// Configures a `Mutex` before creating it; see `Mutex::builder`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MutexBuilder {
+    pub(crate) fairness: Fairness,
+    pub(crate) wake: WakeStrategy,
+    pub(crate) poisonable: bool,
+    pub(crate) name: Option<&'static str>,
+    pub(crate) level: Option<u32>,
+    #[cfg(feature = "watchdog")]
+    pub(crate) warn_after: Option<Duration>,
+    #[cfg(feature = "watchdog")]
+    pub(crate) on_long_hold: Option<fn(&LongHold)>,
+    #[cfg(feature = "web")]
+    pub(crate) warn_wait_after: Option<Duration>,
+}
+
+impl MutexBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Waiters with a higher priority are still served first whatever the
+    /// policy; it only orders waiters of equal priority.
+    pub fn fairness(mut self, fairness: Fairness) -> Self {
+        self.fairness = fairness;
+        self
+    }
+
+    pub fn wake_strategy(mut self, wake: WakeStrategy) -> Self {
+        self.wake = wake;
+        self
+    }
+
+    /// See `Mutex::new_poisonable`.
+    pub fn poisonable(mut self, poisonable: bool) -> Self {
+        self.poisonable = poisonable;
+        self
+    }
+
+    /// Labels the mutex in its `Debug` output and in every diagnostic the
+    /// crate's optional features produce.
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Tasks must take leveled mutexes in increasing level order. Debug
+    /// builds panic when a task wrapped in `task_scope` locks a mutex while
+    /// holding one of the same or a higher level, or log it with
+    /// `panic_free`.
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Reports guards held for longer than `threshold`, through
+    /// `warn_long_hold` unless `on_long_hold` sets another handler. A guard
+    /// is reported when it is released, or earlier if another future queues
+    /// up behind it, and at most once.
+    #[cfg(feature = "watchdog")]
+    pub fn warn_after(mut self, threshold: Duration) -> Self {
+        self.warn_after = Some(threshold);
+        self
+    }
+
+    /// Replaces the handler that `warn_after` reports long holds to.
+    #[cfg(feature = "watchdog")]
+    pub fn on_long_hold(mut self, handler: fn(&LongHold)) -> Self {
+        self.on_long_hold = Some(handler);
+        self
+    }
+
+    /// Replaces the 100 ms that a future may wait for the lock before the
+    /// `web` feature logs a console warning about it.
+    #[cfg(feature = "web")]
+    pub fn warn_wait_after(mut self, threshold: Duration) -> Self {
+        self.warn_wait_after = Some(threshold);
+        self
+    }
+
+    pub fn build<T>(self, value: T) -> Mutex<T> {
+        Mutex::with_options(value, self)
+    }
+}
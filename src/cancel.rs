@@ -0,0 +1,187 @@
+use core::cell::RefCell;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use alloc::vec::Vec;
+use crate::{Pointer, WeakPointer, Mutex, MutexRef, LockFuture, Cancelled};
+use crate::waiters::{WakerId, Waiters};
+
+type Node = Pointer<RefCell<TokenState>>;
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: bool,
+    waiters: Waiters,
+    // Children are held strongly so that a subtree stays reachable while
+    // only its leaves have handles left; a token whose last handle goes
+    // away hands its children to its parent instead.
+    parent: Option<WeakPointer<RefCell<TokenState>>>,
+    children: Vec<Node>,
+    handles: usize,
+}
+
+/// Signals cancellation to every future waiting on it, and on its child
+/// tokens. Clones share the same token. It needs no runtime, so it can
+/// cancel work spawned on any executor.
+#[derive(Debug)]
+pub struct CancellationToken {
+    state: Node,
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        CancellationToken::with_parent(None)
+    }
+}
+
+impl Clone for CancellationToken {
+    fn clone(&self) -> Self {
+        (*self.state).borrow_mut().handles += 1;
+        CancellationToken { state: self.state.clone() }
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn with_parent(parent: Option<WeakPointer<RefCell<TokenState>>>) -> Self {
+        let state = TokenState { parent, handles: 1, ..Default::default() };
+        CancellationToken { state: Pointer::new(RefCell::new(state)) }
+    }
+
+    /// Returns a token that is cancelled along with this one, but can also
+    /// be cancelled on its own without affecting this one.
+    pub fn child_token(&self) -> CancellationToken {
+        let mut state = (*self.state).borrow_mut();
+        if state.cancelled {
+            let child = CancellationToken::new();
+            (*child.state).borrow_mut().cancelled = true;
+            return child;
+        }
+        let child = CancellationToken::with_parent(Some(Pointer::downgrade(&self.state)));
+        state.children.push(child.state.clone());
+        child
+    }
+
+    /// Cancels this token and all of its descendants, waking every future
+    /// waiting on them. Cancelling again does nothing.
+    pub fn cancel(&self) {
+        let mut wakers = Vec::new();
+        let mut tokens = alloc::vec![self.state.clone()];
+        while let Some(token) = tokens.pop() {
+            let mut state = (*token).borrow_mut();
+            // its descendants were cancelled with it
+            if mem::replace(&mut state.cancelled, true) {
+                continue;
+            }
+            wakers.extend(state.waiters.drain());
+            // a cancelled token has nothing left to pass on, so it lets go
+            // of its children and of its place under its parent
+            state.parent = None;
+            tokens.append(&mut state.children);
+        }
+        self.detach();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        (*self.state).borrow().cancelled
+    }
+
+    /// Resolves once the token is cancelled.
+    pub fn cancelled(&self) -> WaitForCancellation<'_> {
+        WaitForCancellation {
+            waker_id: (*self.state).borrow_mut().waiters.next_id(),
+            token: self,
+        }
+    }
+}
+
+impl CancellationToken {
+    // Removes this token from its parent's children, if it is still there.
+    fn detach(&self) {
+        let parent = (*self.state).borrow_mut().parent.take();
+        if let Some(parent) = parent.and_then(|parent| parent.upgrade()) {
+            (*parent).borrow_mut().children.retain(|child| !Pointer::ptr_eq(child, &self.state));
+        }
+    }
+}
+
+impl Drop for CancellationToken {
+    // Once nothing can cancel this token directly, its children only hear
+    // about cancellation through its parent, so they move up a level.
+    fn drop(&mut self) {
+        let (parent, children) = {
+            let mut state = (*self.state).borrow_mut();
+            state.handles -= 1;
+            if state.handles > 0 {
+                return;
+            }
+            (state.parent.clone(), mem::take(&mut state.children))
+        };
+        let parent = parent.and_then(|parent| parent.upgrade());
+        for child in &children {
+            (*child).borrow_mut().parent = parent.as_ref().map(Pointer::downgrade);
+        }
+        self.detach();
+        if let Some(parent) = parent {
+            (*parent).borrow_mut().children.extend(children);
+        }
+    }
+}
+
+pub struct WaitForCancellation<'a> {
+    waker_id: WakerId,
+    token: &'a CancellationToken,
+}
+
+impl <'a> Future for WaitForCancellation<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = (*self.token.state).borrow_mut();
+        if state.cancelled {
+            Poll::Ready(())
+        } else {
+            state.waiters.register(self.waker_id, cx.waker(), ());
+            Poll::Pending
+        }
+    }
+}
+
+impl <'a> Drop for WaitForCancellation<'a> {
+    fn drop(&mut self) {
+        (*self.token.state).borrow_mut().waiters.cancel(self.waker_id);
+    }
+}
+
+impl <T: ?Sized> Mutex<T> {
+    /// Waits for the lock unless `token` is cancelled first, in which case
+    /// the place in the queue is given up. A token that is already cancelled
+    /// fails without taking the lock, even if it is free.
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn lock_or_cancelled<'a>(&'a self, token: &'a CancellationToken) -> LockOrCancelled<'a, T> {
+        LockOrCancelled {
+            lock: self.lock(),
+            cancelled: token.cancelled(),
+        }
+    }
+}
+
+pub struct LockOrCancelled<'a, T: ?Sized> {
+    lock: LockFuture<'a, T>,
+    cancelled: WaitForCancellation<'a>,
+}
+
+impl <'a, T: ?Sized> Future for LockOrCancelled<'a, T> {
+    type Output = Result<MutexRef<'a, T>, Cancelled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if Pin::new(&mut self.cancelled).poll(cx).is_ready() {
+            return Poll::Ready(Err(Cancelled(())));
+        }
+        Pin::new(&mut self.lock).poll(cx).map(Ok)
+    }
+}
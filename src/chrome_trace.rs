@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::string::String;
+use std::vec::Vec;
+use crate::clock::Instant;
+
+struct Event {
+    name: String,
+    mutex: usize,
+    // microseconds since `start_trace`
+    start: f64,
+    duration: f64,
+    task: u64,
+}
+
+struct Recorder {
+    origin: Instant,
+    events: Vec<Event>,
+}
+
+std::thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// When a wait or hold that is being recorded began, and in which task.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Began {
+    at: Instant,
+    task: u64,
+}
+
+/// Starts a wait or hold, unless nothing is being recorded.
+pub(crate) fn begin() -> Option<Began> {
+    RECORDER.with_borrow(|recorder| {
+        recorder.as_ref().map(|_| Began {
+            at: Instant::now(),
+            // futures outside any `task_scope` share track 0
+            task: crate::current_task().map_or(0, |task| task.0 + 1),
+        })
+    })
+}
+
+/// Records the wait or hold that began at `began` as ending now.
+pub(crate) fn end(began: Began, name: Option<&'static str>, mutex: usize, kind: &str) {
+    let now = Instant::now();
+    RECORDER.with_borrow_mut(|recorder| {
+        // a trace restarted in between has a later origin
+        let Some(recorder) = recorder.as_mut().filter(|recorder| recorder.origin <= began.at) else { return };
+        let name = match name {
+            Some(name) => std::format!("{} {}", name, kind),
+            None => std::format!("Mutex@{:#x} {}", mutex, kind),
+        };
+        recorder.events.push(Event {
+            name,
+            mutex,
+            start: micros(began.at.duration_since(recorder.origin)),
+            duration: micros(now.duration_since(began.at)),
+            task: began.task,
+        });
+    });
+}
+
+fn micros(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
+
+/// Starts recording every wait for and hold of a `Mutex` on this thread,
+/// discarding anything recorded before.
+pub fn start_trace() {
+    RECORDER.set(Some(Recorder { origin: Instant::now(), events: Vec::new() }));
+}
+
+/// Stops recording and returns what was recorded since `start_trace`, in
+/// the Chrome Trace Event JSON format that `chrome://tracing` and Perfetto
+/// load. Each task wrapped in `task_scope` gets a track of its own.
+///
+/// Waits and holds are recorded once they end, so those still going on are
+/// left out, as are waits that were cancelled.
+pub fn stop_trace() -> String {
+    let events = RECORDER.take().map(|recorder| recorder.events).unwrap_or_default();
+    let tasks: BTreeSet<u64> = events.iter().map(|event| event.task).collect();
+    let mut entries = Vec::with_capacity(tasks.len() + events.len());
+    for task in tasks {
+        let name = match task {
+            0 => String::from("unscoped"),
+            task => std::format!("task #{}", task - 1),
+        };
+        entries.push(std::format!("{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":\"{}\"}}}}", task, name));
+    }
+    for event in &events {
+        let mut entry = String::from("{\"name\":\"");
+        escape(&event.name, &mut entry);
+        let _ = write!(
+            entry,
+            "\",\"cat\":\"wasm_mutex\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{},\"args\":{{\"mutex\":\"{:#x}\"}}}}",
+            event.start, event.duration, event.task, event.mutex,
+        );
+        entries.push(entry);
+    }
+    std::format!("{{\"traceEvents\":[{}]}}", entries.join(","))
+}
+
+fn escape(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(any(all(target_arch = "wasm32", target_os = "unknown"), feature = "timers"))]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    /// Milliseconds since the page's time origin.
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    pub(crate) fn performance_now() -> f64;
+}
+
+// Only what `timers` needs is compiled without the features that time waits
+// and holds.
+#[cfg(any(feature = "stats", feature = "watchdog", feature = "chrome_trace", feature = "web"))]
+mod instant {
+    // `std::time::Instant` panics on wasm32-unknown-unknown, where the
+    // browser's monotonic clock is `performance.now()` instead.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    mod web {
+        use std::time::Duration;
+        use crate::clock::performance_now;
+
+        /// Milliseconds since the page's time origin.
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+        pub(crate) struct Instant(f64);
+
+        impl Instant {
+            pub(crate) fn now() -> Self {
+                Instant(performance_now())
+            }
+
+            pub(crate) fn duration_since(&self, earlier: Instant) -> Duration {
+                Duration::from_secs_f64((self.0 - earlier.0).max(0.0) / 1000.0)
+            }
+
+            pub(crate) fn elapsed(&self) -> Duration {
+                Instant::now().duration_since(*self)
+            }
+        }
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    pub(crate) use web::Instant;
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) use std::time::Instant;
+}
+
+#[cfg(any(feature = "stats", feature = "watchdog", feature = "chrome_trace", feature = "web"))]
+pub(crate) use instant::Instant;
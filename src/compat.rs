@@ -0,0 +1,11 @@
+/// The surface of `futures::lock::Mutex` under its own names. These are
+/// aliases, so guards and futures from either naming mix freely. Because a
+/// `Mutex` here is a shared handle, `into_inner` and `get_mut` only succeed
+/// on the last handle to the value and return `Result` and `Option`.
+pub mod futures;
+
+/// The names `tokio::sync` uses for its mutex and read-write lock, plus
+/// `AsyncLock` and `AsyncRwLock` impls for tokio's own locks, so code that is
+/// generic over those traits accepts either side of an isomorphic codebase.
+#[cfg(feature = "tokio")]
+pub mod tokio;
@@ -0,0 +1,8 @@
+pub use crate::Mutex;
+pub use crate::WeakMutex as Weak;
+
+pub type MutexGuard<'a, T> = crate::MutexRef<'a, T>;
+pub type MappedMutexGuard<'a, T> = crate::MappedMutexRef<'a, T>;
+pub type OwnedMutexGuard<T> = crate::OwnedMutexRef<T>;
+pub type MutexLockFuture<'a, T> = crate::LockFuture<'a, T>;
+pub type OwnedMutexLockFuture<T> = crate::OwnedLockFuture<T>;
@@ -0,0 +1,45 @@
+use std::future::Future;
+use crate::{AsyncLock, AsyncRwLock};
+
+pub use crate::{Mutex, RwLock};
+
+pub type MutexGuard<'a, T> = crate::MutexRef<'a, T>;
+pub type MappedMutexGuard<'a, T> = crate::MappedMutexRef<'a, T>;
+pub type OwnedMutexGuard<T> = crate::OwnedMutexRef<T>;
+pub type RwLockReadGuard<'a, T> = crate::RwLockReadRef<'a, T>;
+pub type RwLockWriteGuard<'a, T> = crate::RwLockWriteRef<'a, T>;
+
+impl <T: ?Sized> AsyncLock for ::tokio::sync::Mutex<T> {
+    type Target = T;
+    type Guard<'a> = ::tokio::sync::MutexGuard<'a, T> where Self: 'a;
+
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+        ::tokio::sync::Mutex::lock(self)
+    }
+
+    fn try_lock(&self) -> Option<Self::Guard<'_>> {
+        ::tokio::sync::Mutex::try_lock(self).ok()
+    }
+}
+
+impl <T: ?Sized> AsyncRwLock for ::tokio::sync::RwLock<T> {
+    type Target = T;
+    type ReadGuard<'a> = ::tokio::sync::RwLockReadGuard<'a, T> where Self: 'a;
+    type WriteGuard<'a> = ::tokio::sync::RwLockWriteGuard<'a, T> where Self: 'a;
+
+    fn read(&self) -> impl Future<Output = Self::ReadGuard<'_>> {
+        ::tokio::sync::RwLock::read(self)
+    }
+
+    fn write(&self) -> impl Future<Output = Self::WriteGuard<'_>> {
+        ::tokio::sync::RwLock::write(self)
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        ::tokio::sync::RwLock::try_read(self).ok()
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        ::tokio::sync::RwLock::try_write(self).ok()
+    }
+}
@@ -0,0 +1,81 @@
+use core::cell::RefCell;
+use core::task::{Waker, Context, Poll};
+use core::future::poll_fn;
+use alloc::vec::Vec;
+use crate::{Pointer, MutexRef};
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug, Default)]
+struct CondvarState {
+    waiters: Waiters,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Condvar {
+    state: Pointer<RefCell<CondvarState>>,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub async fn wait<'a, T: ?Sized>(&self, guard: MutexRef<'a, T>) -> MutexRef<'a, T> {
+        let mutex = guard.mutex;
+        let waiting = Waiting {
+            waker_id: (*self.state).borrow_mut().waiters.next_id(),
+            condvar: self,
+        };
+
+        // The guard is only released once the waiter is queued, so a
+        // notification sent right after the release can't be missed.
+        let mut guard = Some(guard);
+        poll_fn(|cx| {
+            let poll = self.poll_notified(waiting.waker_id, cx);
+            guard.take();
+            poll
+        }).await;
+
+        mutex.lock().await
+    }
+
+    pub fn notify_one(&self) {
+        let waker = (*self.state).borrow_mut().waiters.grant_front();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub fn notify_all(&self) {
+        let wakers: Vec<_> = {
+            let mut state = (*self.state).borrow_mut();
+            core::iter::from_fn(|| state.waiters.grant_front()).collect()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    fn poll_notified(&self, waker_id: WakerId, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = (*self.state).borrow_mut();
+        if state.waiters.take_grant(waker_id) {
+            Poll::Ready(())
+        } else {
+            state.waiters.register(waker_id, cx.waker(), ());
+            Poll::Pending
+        }
+    }
+}
+
+struct Waiting<'a> {
+    waker_id: WakerId,
+    condvar: &'a Condvar,
+}
+
+impl <'a> Drop for Waiting<'a> {
+    fn drop(&mut self) {
+        let granted = (*self.condvar.state).borrow_mut().waiters.cancel(self.waker_id);
+        // a notification consumed by a cancelled wait goes to the next waiter
+        if granted {
+            self.condvar.notify_one();
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Diagnostics go to the browser console on the web, and to stderr anywhere
+// else.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod web {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[cfg(feature = "registry")]
+        #[wasm_bindgen(js_namespace = console, js_name = log)]
+        pub(crate) fn log(message: &str);
+
+        #[cfg(any(feature = "watchdog", feature = "web"))]
+        #[wasm_bindgen(js_namespace = console, js_name = warn)]
+        pub(crate) fn warn(message: &str);
+
+        #[cfg(all(feature = "panic_free", feature = "std", debug_assertions))]
+        #[wasm_bindgen(js_namespace = console, js_name = error)]
+        pub(crate) fn error(message: &str);
+    }
+}
+
+#[cfg(all(feature = "registry", target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use web::log;
+#[cfg(all(any(feature = "watchdog", feature = "web"), target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use web::warn;
+#[cfg(all(feature = "panic_free", feature = "std", debug_assertions, target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use web::error;
+
+#[cfg(all(feature = "registry", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+pub(crate) fn log(message: &str) {
+    std::eprintln!("{}", message);
+}
+
+#[cfg(all(any(feature = "watchdog", feature = "web"), not(all(target_arch = "wasm32", target_os = "unknown"))))]
+pub(crate) fn warn(message: &str) {
+    std::eprintln!("{}", message);
+}
+
+#[cfg(all(feature = "panic_free", feature = "std", debug_assertions, not(all(target_arch = "wasm32", target_os = "unknown"))))]
+pub(crate) fn error(message: &str) {
+    std::eprintln!("{}", message);
+}
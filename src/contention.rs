@@ -0,0 +1,48 @@
+use std::format;
+use std::string::String;
+use std::time::Duration;
+use crate::clock::Instant;
+use crate::console;
+
+// used unless `MutexBuilder::warn_wait_after` sets a threshold
+const DEFAULT_THRESHOLD: Duration = Duration::from_millis(100);
+// a mutex warns at most this often
+const MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+// Per-mutex bookkeeping for waits that took too long.
+#[derive(Debug, Default)]
+pub(crate) struct WaitWarnings {
+    pub(crate) threshold: Option<Duration>,
+    last: Option<Instant>,
+    suppressed: u32,
+}
+
+impl WaitWarnings {
+    /// Warns if a future has been queued since `queued_at` for longer than
+    /// the threshold, unless the mutex warned recently.
+    pub(crate) fn check(&mut self, queued_at: Instant, name: Option<&'static str>, mutex: usize) {
+        let waited = queued_at.elapsed();
+        if waited <= self.threshold.unwrap_or(DEFAULT_THRESHOLD) {
+            return;
+        }
+        if self.last.is_some_and(|last| last.elapsed() < MIN_INTERVAL) {
+            self.suppressed += 1;
+            return;
+        }
+        let name = match name {
+            Some(name) => String::from(name),
+            None => format!("Mutex@{:#x}", mutex),
+        };
+        let suppressed = match core::mem::take(&mut self.suppressed) {
+            0 => String::new(),
+            n => format!(" ({} more slow waits since the last warning)", n),
+        };
+        console::warn(&format!(
+            "wasm_mutex: a future spent {:.1} ms queued on {}{}",
+            waited.as_secs_f64() * 1000.0,
+            name,
+            suppressed,
+        ));
+        self.last = Some(Instant::now());
+    }
+}
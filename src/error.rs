@@ -0,0 +1,135 @@
+use core::error::Error;
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutError(pub(crate) ());
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out waiting for the lock")
+    }
+}
+
+impl Error for TimeoutError {}
+
+/// The `CancellationToken` given to `Mutex::lock_or_cancelled` was
+/// cancelled first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled(pub(crate) ());
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled while waiting for the lock")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// The `AbortSignal` given to `Mutex::lock_with_signal` aborted first.
+#[cfg(feature = "abort_signal")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbortError(pub(crate) ());
+
+#[cfg(feature = "abort_signal")]
+impl fmt::Display for AbortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the signal aborted while waiting for the lock")
+    }
+}
+
+#[cfg(feature = "abort_signal")]
+impl Error for AbortError {}
+
+/// Why `Mutex::lock_checked` resolved without a guard.
+#[cfg(feature = "panic_free")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockError {
+    /// The future had already resolved to a guard.
+    Completed,
+    /// The mutex's bookkeeping was borrowed when the future was polled.
+    Inconsistent,
+}
+
+#[cfg(feature = "panic_free")]
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Completed => write!(f, "lock future polled after completion"),
+            LockError::Inconsistent => write!(f, "mutex state was already borrowed"),
+        }
+    }
+}
+
+#[cfg(feature = "panic_free")]
+impl Error for LockError {}
+
+/// Returned in place of a guard when a poisonable mutex was released by a
+/// guard that was dropped while panicking. The guard is still usable.
+pub struct PoisonError<G> {
+    guard: G,
+}
+
+impl <G> PoisonError<G> {
+    pub(crate) fn new(guard: G) -> Self {
+        PoisonError { guard }
+    }
+
+    pub fn into_inner(self) -> G {
+        self.guard
+    }
+
+    pub fn get_ref(&self) -> &G {
+        &self.guard
+    }
+
+    pub fn get_mut(&mut self) -> &mut G {
+        &mut self.guard
+    }
+}
+
+impl <G> fmt::Debug for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoisonError").finish_non_exhaustive()
+    }
+}
+
+impl <G> fmt::Display for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "poisoned lock: another task panicked while holding it")
+    }
+}
+
+impl <G> Error for PoisonError<G> {}
+
+/// Why `Mutex::try_lock_result` could not hand out a clean guard.
+pub enum TryLockError<G> {
+    Poisoned(PoisonError<G>),
+    /// The lock is held, or is being handed to a queued waiter.
+    WouldBlock,
+}
+
+impl <G> From<PoisonError<G>> for TryLockError<G> {
+    fn from(error: PoisonError<G>) -> Self {
+        TryLockError::Poisoned(error)
+    }
+}
+
+impl <G> fmt::Debug for TryLockError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryLockError::Poisoned(error) => f.debug_tuple("Poisoned").field(error).finish(),
+            TryLockError::WouldBlock => write!(f, "WouldBlock"),
+        }
+    }
+}
+
+impl <G> fmt::Display for TryLockError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryLockError::Poisoned(error) => error.fmt(f),
+            TryLockError::WouldBlock => write!(f, "try_lock failed because the operation would block"),
+        }
+    }
+}
+
+impl <G> Error for TryLockError<G> {}
@@ -0,0 +1,111 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::hash::{BuildHasher, Hash};
+use crate::Mutex;
+
+/// Map operations on a `Mutex<HashMap<K, V>>` that each hold the lock only
+/// for as long as the operation itself.
+pub trait MutexMapExt<K, V> {
+    /// Returns a copy of the value under `key`, inserting the output of `f`
+    /// first if there is none. The lock is not held while `f` runs; if another
+    /// task inserts the key in the meantime, its value is kept instead.
+    fn entry_with<F, Fut>(&self, key: K, f: F) -> impl Future<Output = V>
+    where F: FnOnce() -> Fut, Fut: Future<Output = V>, V: Clone;
+
+    /// Returns a copy of the value under `key`. Named apart from
+    /// `Mutex::get_cloned`, which would otherwise shadow it.
+    fn get_value<Q>(&self, key: &Q) -> impl Future<Output = Option<V>>
+    where K: Borrow<Q>, Q: Hash + Eq + ?Sized, V: Clone;
+
+    fn insert(&self, key: K, value: V) -> impl Future<Output = Option<V>>;
+
+    fn remove<Q>(&self, key: &Q) -> impl Future<Output = Option<V>>
+    where K: Borrow<Q>, Q: Hash + Eq + ?Sized;
+}
+
+impl <K, V, S> MutexMapExt<K, V> for Mutex<HashMap<K, V, S>>
+where K: Eq + Hash, S: BuildHasher {
+    async fn entry_with<F, Fut>(&self, key: K, f: F) -> V
+    where F: FnOnce() -> Fut, Fut: Future<Output = V>, V: Clone {
+        if let Some(value) = self.lock().await.get(&key) {
+            return value.clone();
+        }
+        let value = f().await;
+        self.lock().await.entry(key).or_insert(value).clone()
+    }
+
+    async fn get_value<Q>(&self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: Hash + Eq + ?Sized, V: Clone {
+        self.lock().await.get(key).cloned()
+    }
+
+    async fn insert(&self, key: K, value: V) -> Option<V> {
+        self.lock().await.insert(key, value)
+    }
+
+    async fn remove<Q>(&self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.lock().await.remove(key)
+    }
+}
+
+/// Collection operations on a `Mutex<Vec<T>>` or `Mutex<VecDeque<T>>` that
+/// each hold the lock only for as long as the operation itself. A `VecDeque`
+/// behaves as a queue: `pop` takes the oldest item.
+pub trait MutexVecExt<T> {
+    fn push(&self, value: T) -> impl Future<Output = ()>;
+
+    fn pop(&self) -> impl Future<Output = Option<T>>;
+
+    /// Moves every item onto the end of `out`, oldest first.
+    fn drain_into(&self, out: &mut Vec<T>) -> impl Future<Output = ()>;
+
+    fn len(&self) -> impl Future<Output = usize>;
+
+    fn is_empty(&self) -> impl Future<Output = bool>;
+}
+
+impl <T> MutexVecExt<T> for Mutex<Vec<T>> {
+    async fn push(&self, value: T) {
+        self.lock().await.push(value);
+    }
+
+    async fn pop(&self) -> Option<T> {
+        self.lock().await.pop()
+    }
+
+    async fn drain_into(&self, out: &mut Vec<T>) {
+        out.append(&mut *self.lock().await);
+    }
+
+    async fn len(&self) -> usize {
+        self.lock().await.len()
+    }
+
+    async fn is_empty(&self) -> bool {
+        self.lock().await.is_empty()
+    }
+}
+
+impl <T> MutexVecExt<T> for Mutex<VecDeque<T>> {
+    async fn push(&self, value: T) {
+        self.lock().await.push_back(value);
+    }
+
+    async fn pop(&self) -> Option<T> {
+        self.lock().await.pop_front()
+    }
+
+    async fn drain_into(&self, out: &mut Vec<T>) {
+        out.extend(self.lock().await.drain(..));
+    }
+
+    async fn len(&self) -> usize {
+        self.lock().await.len()
+    }
+
+    async fn is_empty(&self) -> bool {
+        self.lock().await.is_empty()
+    }
+}
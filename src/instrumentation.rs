@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use crate::Pointer;
+
+/// The mutex an instrumentation callback is about.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockEvent {
+    /// The name given to `MutexBuilder::name`, if any.
+    pub name: Option<&'static str>,
+    /// Identifies the mutex for as long as it lives; clones share it.
+    pub address: usize,
+}
+
+/// Callbacks for feeding lock telemetry into an app's own metrics, installed
+/// with `set_instrumentation`. Every method does nothing by default.
+///
+/// Callbacks run after the mutex has updated its state, so they may use the
+/// mutex themselves.
+pub trait LockInstrumentation {
+    /// A `try_lock` was called, or a lock future was polled for the first
+    /// time.
+    fn on_lock_attempt(&self, _lock: &LockEvent) {}
+
+    /// A lock future found the lock held and queued up for it.
+    fn on_contended(&self, _lock: &LockEvent) {}
+
+    /// A guard was handed out, `contended` if its future had to queue.
+    fn on_acquired(&self, _lock: &LockEvent, _contended: bool) {}
+
+    /// A guard gave the lock up.
+    fn on_released(&self, _lock: &LockEvent) {}
+}
+
+std::thread_local! {
+    static HOOKS: RefCell<Option<Pointer<dyn LockInstrumentation>>> = const { RefCell::new(None) };
+}
+
+/// Sends the events of every `Mutex` on this thread to `hooks`, replacing
+/// any that were set before.
+pub fn set_instrumentation(hooks: impl LockInstrumentation + 'static) {
+    HOOKS.set(Some(Pointer::new(hooks)));
+}
+
+/// Removes the hooks installed by `set_instrumentation`.
+pub fn clear_instrumentation() {
+    HOOKS.set(None);
+}
+
+pub(crate) fn emit(name: Option<&'static str>, address: usize, f: impl FnOnce(&dyn LockInstrumentation, &LockEvent)) {
+    // cloned out so that a callback can replace the hooks
+    if let Some(hooks) = HOOKS.with_borrow(Option::clone) {
+        f(&*hooks, &LockEvent { name, address });
+    }
+}
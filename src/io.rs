@@ -0,0 +1,79 @@
+use std::io;
+use std::pin::Pin;
+use std::future::Future;
+use std::task::{ready, Context, Poll};
+use futures_io::{AsyncRead, AsyncWrite};
+use crate::{Mutex, OwnedLockFuture, OwnedMutexRef};
+
+/// Shares one reader or writer between tasks by locking the mutex for each
+/// read or write. Every task wraps its own handle to the mutex; an
+/// individual read or write is never interleaved with another task's, but a
+/// `write_all` that takes several of them can be. An operation that returns
+/// `Pending` keeps the lock until it completes, so only the task holding the
+/// lock is ever parked on the inner reader or writer.
+pub struct GuardedIo<T: ?Sized> {
+    mutex: Mutex<T>,
+    lock: Option<OwnedLockFuture<T>>,
+    guard: Option<OwnedMutexRef<T>>,
+}
+
+impl <T: ?Sized> GuardedIo<T> {
+    pub fn new(mutex: Mutex<T>) -> Self {
+        GuardedIo { mutex, lock: None, guard: None }
+    }
+
+    pub fn get_ref(&self) -> &Mutex<T> {
+        &self.mutex
+    }
+
+    pub fn into_inner(self) -> Mutex<T> {
+        self.mutex
+    }
+
+    // Waits for the lock, keeping the place in the queue across polls, and
+    // releases it again once `f` is ready. Releasing it while `f` is pending
+    // would let another task's poll replace the waker the inner IO stored.
+    fn poll_locked<R>(&mut self, cx: &mut Context<'_>, f: impl FnOnce(Pin<&mut T>, &mut Context<'_>) -> Poll<R>) -> Poll<R>
+    where T: Unpin {
+        let guard = match self.guard {
+            Some(ref mut guard) => guard,
+            None => {
+                let lock = self.lock.get_or_insert_with(|| self.mutex.lock_owned());
+                let guard = ready!(Pin::new(lock).poll(cx));
+                self.lock = None;
+                self.guard.insert(guard)
+            }
+        };
+        let result = ready!(f(Pin::new(&mut **guard), cx));
+        self.guard = None;
+        Poll::Ready(result)
+    }
+}
+
+impl <T: AsyncRead + Unpin + ?Sized> AsyncRead for GuardedIo<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_locked(cx, |io, cx| io.poll_read(cx, buf))
+    }
+
+    fn poll_read_vectored(self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &mut [io::IoSliceMut<'_>]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_locked(cx, |io, cx| io.poll_read_vectored(cx, bufs))
+    }
+}
+
+impl <T: AsyncWrite + Unpin + ?Sized> AsyncWrite for GuardedIo<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_locked(cx, |io, cx| io.poll_write(cx, buf))
+    }
+
+    fn poll_write_vectored(self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_locked(cx, |io, cx| io.poll_write_vectored(cx, bufs))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_locked(cx, |io, cx| io.poll_flush(cx))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_locked(cx, |io, cx| io.poll_close(cx))
+    }
+}
@@ -0,0 +1,118 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::task::{Context, Poll};
+use std::future::Future;
+use std::pin::Pin;
+use crate::{Mutex, OwnedMutexRef, OwnedLockFuture, Pointer};
+
+/// Hands out one lock per key. An entry only exists while its key is locked
+/// or waited on, so idle keys take no space.
+pub struct KeyedMutex<K> {
+    locks: Pointer<RefCell<HashMap<K, Mutex<()>>>>,
+}
+
+impl <K> Clone for KeyedMutex<K> {
+    fn clone(&self) -> Self {
+        KeyedMutex { locks: self.locks.clone() }
+    }
+}
+
+impl <K> Default for KeyedMutex<K> {
+    fn default() -> Self {
+        KeyedMutex { locks: Default::default() }
+    }
+}
+
+impl <K: Eq + Hash + Clone> KeyedMutex<K> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn lock(&self, key: K) -> KeyedLockFuture<'_, K> {
+        let lock = self.entry(&key).lock_owned();
+        KeyedLockFuture { keyed: self, key, lock: Some(lock) }
+    }
+
+    pub fn try_lock(&self, key: K) -> Option<KeyedMutexRef<'_, K>> {
+        let guard = self.entry(&key).try_lock_owned();
+        match guard {
+            Some(guard) => Some(KeyedMutexRef { keyed: self, key, guard: Some(guard) }),
+            None => {
+                self.cleanup(&key);
+                None
+            }
+        }
+    }
+
+    /// Number of keys that are locked or waited on.
+    pub fn len(&self) -> usize {
+        (*self.locks).borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (*self.locks).borrow().is_empty()
+    }
+
+    fn entry(&self, key: &K) -> Mutex<()> {
+        (*self.locks).borrow_mut().entry(key.clone()).or_default().clone()
+    }
+
+    // Drops the entry once the map holds the only handle to it.
+    fn cleanup(&self, key: &K) {
+        let mut locks = (*self.locks).borrow_mut();
+        if locks.get(key).is_some_and(|mutex| mutex.handle_count() == 1) {
+            locks.remove(key);
+        }
+    }
+}
+
+pub struct KeyedMutexRef<'a, K: Eq + Hash + Clone> {
+    keyed: &'a KeyedMutex<K>,
+    key: K,
+    guard: Option<OwnedMutexRef<()>>,
+}
+
+impl <'a, K: Eq + Hash + Clone> KeyedMutexRef<'a, K> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl <'a, K: Eq + Hash + Clone> Drop for KeyedMutexRef<'a, K> {
+    fn drop(&mut self) {
+        self.guard = None;
+        self.keyed.cleanup(&self.key);
+    }
+}
+
+pub struct KeyedLockFuture<'a, K: Eq + Hash + Clone> {
+    keyed: &'a KeyedMutex<K>,
+    key: K,
+    lock: Option<OwnedLockFuture<()>>,
+}
+
+// nothing is structurally pinned; the inner lock future is itself Unpin
+impl <'a, K: Eq + Hash + Clone> Unpin for KeyedLockFuture<'a, K> {}
+
+impl <'a, K: Eq + Hash + Clone> Future for KeyedLockFuture<'a, K> {
+    type Output = KeyedMutexRef<'a, K>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        #[cfg(not(feature = "panic_free"))]
+        let lock = self.lock.as_mut().expect("KeyedLockFuture polled after completion");
+        #[cfg(feature = "panic_free")]
+        let Some(lock) = self.lock.as_mut() else { return Poll::Pending };
+        let guard = std::task::ready!(Pin::new(lock).poll(cx));
+        self.lock = None;
+        Poll::Ready(KeyedMutexRef { keyed: self.keyed, key: self.key.clone(), guard: Some(guard) })
+    }
+}
+
+impl <'a, K: Eq + Hash + Clone> Drop for KeyedLockFuture<'a, K> {
+    fn drop(&mut self) {
+        if self.lock.take().is_some() {
+            self.keyed.cleanup(&self.key);
+        }
+    }
+}
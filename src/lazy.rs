@@ -0,0 +1,63 @@
+use core::cell::RefCell;
+use core::future::{Future, poll_fn};
+use core::pin::Pin;
+use core::task::Poll;
+use alloc::boxed::Box;
+use crate::{Pointer, OnceCell};
+
+type Init<F> = Pointer<RefCell<Option<Pin<Box<F>>>>>;
+
+pub struct Lazy<T, F> {
+    cell: OnceCell<T>,
+    init: Init<F>,
+}
+
+impl <T, F> Clone for Lazy<T, F> {
+    fn clone(&self) -> Self {
+        Self { cell: self.cell.clone(), init: self.init.clone() }
+    }
+}
+
+impl <T, F: Future<Output = T>> Lazy<T, F> {
+    pub fn new(init: F) -> Self {
+        Lazy {
+            cell: OnceCell::new(),
+            init: Pointer::new(RefCell::new(Some(Box::pin(init)))),
+        }
+    }
+
+    pub async fn get(&self) -> &T {
+        self.cell.get_or_init(|| self.run()).await
+    }
+
+    // The in-flight initializer is put back if the caller driving it is
+    // cancelled, so the next `get` carries on from where it left off rather
+    // than starting over.
+    async fn run(&self) -> T {
+        let init = (*self.init).borrow_mut().take();
+        #[cfg(not(feature = "panic_free"))]
+        let init = init.expect("Lazy initializer is already running");
+        #[cfg(feature = "panic_free")]
+        let Some(init) = init else { return core::future::pending().await };
+        let mut running = Running { init: &self.init, future: Some(init) };
+        let value = poll_fn(|cx| match running.future.as_mut() {
+            Some(future) => future.as_mut().poll(cx),
+            None => Poll::Pending,
+        }).await;
+        running.future = None;
+        value
+    }
+}
+
+struct Running<'a, F> {
+    init: &'a Init<F>,
+    future: Option<Pin<Box<F>>>,
+}
+
+impl <'a, F> Drop for Running<'a, F> {
+    fn drop(&mut self) {
+        if let Some(future) = self.future.take() {
+            *(**self.init).borrow_mut() = Some(future);
+        }
+    }
+}
@@ -1,163 +1,164 @@
-use std::cell::{RefCell, RefMut};
-use std::task::{Waker, Context, Poll};
-use std::rc::Rc;
-use std::future::Future;
-use std::pin::Pin;
-use std::ops::{Deref, DerefMut};
-use std::marker::PhantomData;
-use serde::{Serialize, Deserialize};
-
-type WakerId = u32;
-
-#[derive(Debug, Clone)]
-struct MutexState {
-    wakers: Vec<(WakerId, Waker)>,
-    next_waker_id: WakerId,
-}
-
-impl Default for MutexState {
-    fn default() -> Self {
-        Self {
-            wakers: Default::default(),
-            next_waker_id: 0,
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Mutex<T: ?Sized> {
-    value: Rc<RefCell<T>>,
-    state: Rc<RefCell<MutexState>>,
-}
-
-impl <T: Default> Default for Mutex<T> {
-    fn default() -> Self {
-        Self { value: Default::default(), state: Default::default() }
-    }
-}
-
-impl <T: Serialize> Serialize for Mutex<T> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where S: serde::Serializer {
-        serializer.serialize_newtype_struct("Mutex", &*self.value)
-    }
-}
-
-impl <'de, T: Deserialize<'de>> Deserialize<'de> for Mutex<T> {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where D: serde::Deserializer<'de> {
-        Ok(Mutex {
-            value: Rc::new(RefCell::deserialize(deserializer)?),
-            state: Default::default(),
-        })
-    }
-}
-
-impl <T> Mutex<T> {
-    pub fn new(value: T) -> Self {
-        Mutex {
-            value: Rc::new(RefCell::new(value)),
-            state: Default::default(),
-        }
-    }
-
-    pub fn lock(&self) -> LockFuture<T> {
-        let waker_id = {
-            let mut state = (*self.state).borrow_mut();
-            let waker_id = state.next_waker_id;
-            state.next_waker_id += 1;
-            waker_id
-        };
-        let state = self.state.clone();
-        LockFuture {
-            waker_id,
-            value: &self.value,
-            state: self.state.clone(),
-            set_wake: Box::new(move |waker_id, waker| {
-                let mut state = (*state).borrow_mut();
-                let index = state.wakers.iter().position(|(id, _waker)| *id == waker_id);
-                if let Some(index) = index {
-                    state.wakers.insert(index, (waker_id, waker));
-                } else {
-                    state.wakers.push((waker_id, waker));
-                }
-            }),
-            phantom: PhantomData
-        }
-    }
-
-    pub fn try_lock(&self) -> Option<MutexRef<T>> {
-        if let Ok(v) = self.value.try_borrow_mut() {
-            let r = MutexRef::new(v, self.state.clone());
-            Some(r)
-        } else {
-            None
-        }
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "panic_free", deny(clippy::unwrap_used, clippy::expect_used, clippy::panic, clippy::unreachable, clippy::todo, clippy::unimplemented, clippy::indexing_slicing))]
+
+extern crate alloc;
+
+#[cfg(all(feature = "timers", target_os = "wasi"))]
+compile_error!("the `timers` feature needs a JavaScript host and is not available on WASI");
+#[cfg(all(feature = "abort_signal", target_os = "wasi"))]
+compile_error!("the `abort_signal` feature needs a JavaScript host and is not available on WASI");
+
+mod waiters;
+mod error;
+mod builder;
+mod mutex;
+mod rwlock;
+mod semaphore;
+mod condvar;
+mod notify;
+mod barrier;
+mod once_cell;
+mod cancel;
+mod lazy;
+#[cfg(feature = "std")]
+mod static_mutex;
+#[cfg(feature = "std")]
+mod ext;
+mod read_handle;
+mod multi;
+#[cfg(feature = "std")]
+mod keyed;
+#[cfg(feature = "std")]
+mod singleflight;
+#[cfg(feature = "std")]
+mod sharded;
+#[cfg(feature = "std")]
+mod lock_map;
+#[cfg(feature = "std")]
+mod task;
+mod reentrant;
+mod traits;
+#[cfg(feature = "timers")]
+mod timeout;
+#[cfg(feature = "abort_signal")]
+mod abort;
+#[cfg(feature = "io")]
+mod io;
+#[cfg(feature = "sink")]
+mod sink;
+#[cfg(any(feature = "lock_api", feature = "embassy"))]
+mod raw;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(any(feature = "stats", feature = "watchdog", feature = "chrome_trace", feature = "web", feature = "timers"))]
+mod clock;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(any(feature = "registry", feature = "watchdog", feature = "web", all(feature = "panic_free", feature = "std", debug_assertions)))]
+mod console;
+#[cfg(feature = "registry")]
+mod registry;
+#[cfg(feature = "watchdog")]
+mod watchdog;
+#[cfg(feature = "web-profiling")]
+mod profiling;
+#[cfg(feature = "chrome_trace")]
+mod chrome_trace;
+#[cfg(feature = "web")]
+mod contention;
+#[cfg(feature = "instrumentation")]
+mod instrumentation;
+
+pub mod watch;
+pub mod oneshot;
+pub mod mpsc;
+pub mod broadcast;
+/// Other async mutexes' names for this crate's types, to ease migrating code.
+pub mod compat;
+/// The single-threaded `Mutex`, also exported at the crate root. Cheap,
+/// but its handles, guards and futures are neither `Send` nor `Sync`.
+pub mod local;
+/// A `Send + Sync` `Mutex` backed by `Arc` and a `std::sync::Mutex`, or a
+/// spin lock without `std`, for state shared with other threads. It compiles alongside `local`, so one
+/// crate graph can use both.
+pub mod sync;
+/// A deterministic executor and lock contenders for testing code built on
+/// this crate's `Mutex`.
+#[cfg(feature = "test_util")]
+pub mod test_util;
+
+pub use error::{TimeoutError, TryLockError, PoisonError, Cancelled};
+#[cfg(feature = "panic_free")]
+pub use error::LockError;
+#[cfg(feature = "abort_signal")]
+pub use error::AbortError;
+pub use builder::{MutexBuilder, Fairness, WakeStrategy};
+pub use mutex::{Mutex, WeakMutex, ByHandle, Subscription, Changed, LockStream, MutexRef, MappedMutexRef, OwnedMutexRef, LockFuture, OwnedLockFuture};
+pub use rwlock::{RwLock, RwLockReadRef, RwLockWriteRef, ReadFuture, WriteFuture};
+pub use semaphore::{Semaphore, Permit, AcquireFuture};
+pub use condvar::Condvar;
+pub use notify::{Notify, Notified};
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use once_cell::OnceCell;
+pub use cancel::{CancellationToken, WaitForCancellation, LockOrCancelled};
+pub use lazy::Lazy;
+#[cfg(feature = "panic_free")]
+pub use mutex::CheckedLockFuture;
+#[cfg(feature = "stats")]
+pub use stats::MutexStats;
+#[cfg(feature = "registry")]
+pub use registry::{dump_locks, lock_report, snapshot, LockSnapshot};
+#[cfg(feature = "watchdog")]
+pub use watchdog::{LongHold, warn_long_hold};
+#[cfg(feature = "chrome_trace")]
+pub use chrome_trace::{start_trace, stop_trace};
+#[cfg(feature = "instrumentation")]
+pub use instrumentation::{set_instrumentation, clear_instrumentation, LockInstrumentation, LockEvent};
+#[cfg(feature = "std")]
+pub use static_mutex::StaticMutex;
+#[cfg(feature = "std")]
+pub use ext::{MutexMapExt, MutexVecExt};
+pub use multi::{lock_both, LockAll, MutexGroup, GroupGuard};
+#[cfg(feature = "std")]
+pub use keyed::{KeyedMutex, KeyedMutexRef, KeyedLockFuture};
+#[cfg(feature = "std")]
+pub use singleflight::Singleflight;
+#[cfg(feature = "std")]
+pub use sharded::{ShardedMutex, StripedLock};
+#[cfg(feature = "std")]
+pub use lock_map::{LockMap, LockMapEntry};
+#[cfg(feature = "std")]
+pub use task::{task_scope, current_task, TaskScope, TaskId};
+pub use reentrant::{ReentrantMutex, ReentrantMutexRef, ReentrantLockFuture, LockOwner};
+pub use traits::{AsyncLock, AsyncRwLock};
+pub use read_handle::{ReadHandle, ReadHandleRef, ReadHandleFuture};
+#[cfg(feature = "timers")]
+pub use timeout::LockTimeout;
+#[cfg(feature = "abort_signal")]
+pub use abort::LockWithSignal;
+#[cfg(feature = "io")]
+pub use io::GuardedIo;
+#[cfg(feature = "sink")]
+pub use sink::GuardedSink;
+#[cfg(any(feature = "lock_api", feature = "embassy"))]
+pub use raw::RawLocalMutex;
+#[cfg(feature = "lock_api")]
+pub use raw::{LocalMutex, LocalMutexGuard, MappedLocalMutexGuard};
+
+/// The shared pointer behind every handle in this crate.
+pub type Pointer<T> = alloc::rc::Rc<T>;
+type WeakPointer<T> = alloc::rc::Weak<T>;
+
+// Stops the program without unwinding, where a misuse leaves nothing sound
+// to hand out and no error to return it in.
+#[cfg(feature = "panic_free")]
+pub(crate) fn abort() -> ! {
+    #[cfg(feature = "std")]
+    std::process::abort();
+    #[cfg(all(not(feature = "std"), target_arch = "wasm32"))]
+    core::arch::wasm32::unreachable();
+    #[cfg(all(not(feature = "std"), not(target_arch = "wasm32")))]
+    loop {
+        core::hint::spin_loop();
     }
 }
-
-pub struct MutexRef<'a, T> {
-    core: RefMut<'a, T>,
-    on_drop: Box<dyn FnMut()>,
-}
-
-impl <'a, T> MutexRef<'a, T> {
-    fn new(core: RefMut<'a, T>, state: Rc<RefCell<MutexState>>) -> Self {
-        MutexRef {
-            core,
-            on_drop: Box::new(move || {
-                let w = {
-                    let mut state = (*state).borrow_mut();
-                    state.wakers.pop()
-                };
-
-                if let Some((_waker_id, waker)) = w {
-                    waker.wake();
-                }
-            }),
-        }
-    }
-}
-
-impl <'a, T> Deref for MutexRef<'a, T> {
-    type Target = T;
-
-    fn deref(&self) -> &Self::Target {
-        &self.core
-    }
-}
-
-impl <'a, T> DerefMut for MutexRef<'a, T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.core
-    }
-}
-
-impl <'a, T> Drop for MutexRef<'a, T> {
-    fn drop(&mut self) {
-        (self.on_drop)();
-    }
-}
-
-pub struct LockFuture<'a, T> {
-    waker_id: WakerId,
-    value: &'a Rc<RefCell<T>>,
-    state: Rc<RefCell<MutexState>>,
-    set_wake: Box<dyn FnMut(WakerId, Waker)>,
-    phantom: PhantomData<&'a T>,
-}
-
-impl <'a, T: 'static> Future for LockFuture<'a, T> {
-    type Output = MutexRef<'a, T>;
-
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if let Ok(v) = self.value.try_borrow_mut() {
-            let r = MutexRef::new(v, self.state.clone());
-            Poll::Ready(r)
-        } else {
-            let waker_id = self.waker_id;
-            (self.set_wake)(waker_id, cx.waker().clone());
-            Poll::Pending
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1 @@
+pub use crate::{Mutex, WeakMutex, MutexRef, MappedMutexRef, OwnedMutexRef, LockFuture, OwnedLockFuture};
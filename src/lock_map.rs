@@ -0,0 +1,124 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use crate::{ShardedMutex, MutexRef, MappedMutexRef};
+
+const DEFAULT_SHARDS: usize = 16;
+
+/// A map split across the shards of a `ShardedMutex`. Each operation locks
+/// only the shard its key belongs to.
+pub struct LockMap<K, V> {
+    shards: ShardedMutex<HashMap<K, V>>,
+}
+
+impl <K, V> Clone for LockMap<K, V> {
+    fn clone(&self) -> Self {
+        LockMap { shards: self.shards.clone() }
+    }
+}
+
+impl <K, V> Default for LockMap<K, V> {
+    fn default() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+}
+
+impl <K, V> LockMap<K, V> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_shards(shards: usize) -> Self {
+        LockMap { shards: ShardedMutex::new(shards) }
+    }
+}
+
+impl <K: Eq + Hash, V> LockMap<K, V> {
+    pub async fn get<Q>(&self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: Hash + Eq + ?Sized, V: Clone {
+        self.shards.lock(key).await.get(key).cloned()
+    }
+
+    pub async fn contains_key<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.shards.lock(key).await.contains_key(key)
+    }
+
+    pub async fn insert(&self, key: K, value: V) -> Option<V> {
+        self.shards.lock(&key).await.insert(key, value)
+    }
+
+    pub async fn remove<Q>(&self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.shards.lock(key).await.remove(key)
+    }
+
+    /// Locks the shard holding `key` for as long as the entry is alive.
+    pub async fn entry(&self, key: K) -> LockMapEntry<'_, K, V> {
+        let guard = self.shards.lock(&key).await;
+        LockMapEntry { guard, key }
+    }
+
+    /// Visits every entry, locking one shard at a time, so the map as a whole
+    /// may change between shards.
+    pub async fn for_each(&self, mut f: impl FnMut(&K, &mut V)) {
+        for shard in self.shards.shards() {
+            for (key, value) in shard.lock().await.iter_mut() {
+                f(key, value);
+            }
+        }
+    }
+
+    /// Copies every entry out, one shard at a time.
+    pub async fn snapshot(&self) -> Vec<(K, V)>
+    where K: Clone, V: Clone {
+        let mut entries = Vec::new();
+        self.for_each(|key, value| entries.push((key.clone(), value.clone()))).await;
+        entries
+    }
+
+    pub async fn len(&self) -> usize {
+        let mut len = 0;
+        for shard in self.shards.shards() {
+            len += shard.lock().await.len();
+        }
+        len
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+pub struct LockMapEntry<'a, K, V> {
+    guard: MutexRef<'a, HashMap<K, V>>,
+    key: K,
+}
+
+impl <'a, K: Eq + Hash, V> LockMapEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> Option<&V> {
+        self.guard.get(&self.key)
+    }
+
+    pub fn or_insert(self, value: V) -> MappedMutexRef<'a, V> {
+        self.or_insert_with(|| value)
+    }
+
+    pub fn or_insert_with(self, f: impl FnOnce() -> V) -> MappedMutexRef<'a, V> {
+        let LockMapEntry { guard, key } = self;
+        MutexRef::map(guard, |map| map.entry(key).or_insert_with(f))
+    }
+
+    pub fn or_default(self) -> MappedMutexRef<'a, V>
+    where V: Default {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn remove(mut self) -> Option<V> {
+        self.guard.remove(&self.key)
+    }
+}
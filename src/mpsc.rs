@@ -0,0 +1,377 @@
+use core::cell::RefCell;
+use alloc::collections::VecDeque;
+use core::error::Error;
+use core::fmt;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug)]
+struct ChanState<T> {
+    queue: VecDeque<T>,
+    capacity: Option<usize>,
+    // slots promised to senders that have been granted but not yet polled
+    reserved: usize,
+    senders: usize,
+    closed: bool,
+    recv_waiters: Waiters,
+    send_waiters: Waiters,
+}
+
+impl <T> ChanState<T> {
+    fn has_room(&self) -> bool {
+        self.capacity.is_none_or(|capacity| self.queue.len() + self.reserved < capacity)
+    }
+
+    fn push(&mut self, value: T) -> Vec<Waker> {
+        self.queue.push_back(value);
+        self.recv_waiters.drain().collect()
+    }
+
+    fn dispatch_senders(&mut self) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        while self.has_room() {
+            match self.send_waiters.grant_front() {
+                Some(waker) => {
+                    self.reserved += 1;
+                    wakers.push(waker);
+                }
+                None => break,
+            }
+        }
+        wakers
+    }
+}
+
+type Chan<T> = Pointer<RefCell<ChanState<T>>>;
+
+fn new_chan<T>(capacity: Option<usize>) -> Chan<T> {
+    Pointer::new(RefCell::new(ChanState {
+        queue: VecDeque::new(),
+        capacity,
+        reserved: 0,
+        senders: 1,
+        closed: false,
+        recv_waiters: Default::default(),
+        send_waiters: Default::default(),
+    }))
+}
+
+fn wake_all(wakers: Vec<Waker>) {
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl <T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl <T: fmt::Debug> Error for SendError<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Closed(T),
+}
+
+impl <T> TrySendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Closed(value) => value,
+        }
+    }
+}
+
+impl <T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "channel full"),
+            TrySendError::Closed(_) => write!(f, "channel closed"),
+        }
+    }
+}
+
+impl <T: fmt::Debug> Error for TrySendError<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "channel empty"),
+            TryRecvError::Disconnected => write!(f, "channel disconnected"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+// A zero buffer panics, or is taken as one with `panic_free`.
+pub fn channel<T>(buffer: usize) -> (Sender<T>, Receiver<T>) {
+    #[cfg(not(feature = "panic_free"))]
+    assert!(buffer > 0, "mpsc bounded channel requires buffer > 0");
+    #[cfg(feature = "panic_free")]
+    let buffer = buffer.max(1);
+    let chan = new_chan(Some(buffer));
+    (Sender { chan: chan.clone() }, Receiver { chan })
+}
+
+pub fn unbounded_channel<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let chan = new_chan(None);
+    (UnboundedSender { chan: chan.clone() }, UnboundedReceiver { inner: Receiver { chan } })
+}
+
+#[derive(Debug)]
+pub struct Sender<T> {
+    chan: Chan<T>,
+}
+
+impl <T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        (*self.chan).borrow_mut().senders += 1;
+        Self { chan: self.chan.clone() }
+    }
+}
+
+impl <T> Sender<T> {
+    pub fn send(&self, value: T) -> SendFuture<'_, T> {
+        SendFuture {
+            waker_id: (*self.chan).borrow_mut().send_waiters.next_id(),
+            chan: &self.chan,
+            value: Some(value),
+        }
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let wakers = {
+            let mut state = (*self.chan).borrow_mut();
+            if state.closed {
+                return Err(TrySendError::Closed(value));
+            }
+            if !state.send_waiters.is_empty() || !state.has_room() {
+                return Err(TrySendError::Full(value));
+            }
+            state.push(value)
+        };
+        wake_all(wakers);
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        (*self.chan).borrow().closed
+    }
+}
+
+impl <T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        drop_sender(&self.chan);
+    }
+}
+
+fn drop_sender<T>(chan: &Chan<T>) {
+    let wakers = {
+        let mut state = (**chan).borrow_mut();
+        state.senders -= 1;
+        if state.senders == 0 {
+            state.recv_waiters.drain().collect()
+        } else {
+            Vec::new()
+        }
+    };
+    wake_all(wakers);
+}
+
+pub struct SendFuture<'a, T> {
+    waker_id: WakerId,
+    chan: &'a Chan<T>,
+    value: Option<T>,
+}
+
+// the value is never pinned, it is only moved into the queue
+impl <'a, T> Unpin for SendFuture<'a, T> {}
+
+impl <'a, T> Future for SendFuture<'a, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let waker_id = self.waker_id;
+        let wakers = {
+            let mut state = (**self.chan).borrow_mut();
+            let granted = state.send_waiters.take_grant(waker_id);
+            if granted {
+                state.reserved -= 1;
+            }
+
+            #[cfg(not(feature = "panic_free"))]
+            let value = self.value.take().expect("SendFuture polled after completion");
+            #[cfg(feature = "panic_free")]
+            let Some(value) = self.value.take() else { return Poll::Pending };
+            if state.closed {
+                return Poll::Ready(Err(SendError(value)));
+            }
+            if !granted && (!state.send_waiters.is_empty() || !state.has_room()) {
+                self.value = Some(value);
+                state.send_waiters.register(waker_id, cx.waker(), ());
+                return Poll::Pending;
+            }
+            state.push(value)
+        };
+        wake_all(wakers);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl <'a, T> Drop for SendFuture<'a, T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = (**self.chan).borrow_mut();
+            if state.send_waiters.cancel(self.waker_id) {
+                state.reserved -= 1;
+            }
+            state.dispatch_senders()
+        };
+        wake_all(wakers);
+    }
+}
+
+#[derive(Debug)]
+pub struct Receiver<T> {
+    chan: Chan<T>,
+}
+
+impl <T> Receiver<T> {
+    pub fn recv(&mut self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            waker_id: (*self.chan).borrow_mut().recv_waiters.next_id(),
+            chan: &self.chan,
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let (value, wakers) = {
+            let mut state = (*self.chan).borrow_mut();
+            match state.queue.pop_front() {
+                Some(value) => (value, state.dispatch_senders()),
+                // nothing can be sent once every sender is gone or the
+                // channel is closed
+                None if state.senders == 0 || state.closed => return Err(TryRecvError::Disconnected),
+                None => return Err(TryRecvError::Empty),
+            }
+        };
+        wake_all(wakers);
+        Ok(value)
+    }
+
+    /// Stops any further sends; values already queued can still be received.
+    pub fn close(&mut self) {
+        let wakers: Vec<_> = {
+            let mut state = (*self.chan).borrow_mut();
+            state.closed = true;
+            state.send_waiters.drain().collect()
+        };
+        wake_all(wakers);
+    }
+}
+
+impl <T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+pub struct RecvFuture<'a, T> {
+    waker_id: WakerId,
+    chan: &'a Chan<T>,
+}
+
+impl <'a, T> Future for RecvFuture<'a, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let (value, wakers) = {
+            let mut state = (**self.chan).borrow_mut();
+            match state.queue.pop_front() {
+                Some(value) => (value, state.dispatch_senders()),
+                None if state.senders == 0 || state.closed => return Poll::Ready(None),
+                None => {
+                    state.recv_waiters.register(self.waker_id, cx.waker(), ());
+                    return Poll::Pending;
+                }
+            }
+        };
+        wake_all(wakers);
+        Poll::Ready(Some(value))
+    }
+}
+
+impl <'a, T> Drop for RecvFuture<'a, T> {
+    fn drop(&mut self) {
+        (**self.chan).borrow_mut().recv_waiters.cancel(self.waker_id);
+    }
+}
+
+#[derive(Debug)]
+pub struct UnboundedSender<T> {
+    chan: Chan<T>,
+}
+
+impl <T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        (*self.chan).borrow_mut().senders += 1;
+        Self { chan: self.chan.clone() }
+    }
+}
+
+impl <T> UnboundedSender<T> {
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let wakers = {
+            let mut state = (*self.chan).borrow_mut();
+            if state.closed {
+                return Err(SendError(value));
+            }
+            state.push(value)
+        };
+        wake_all(wakers);
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        (*self.chan).borrow().closed
+    }
+}
+
+impl <T> Drop for UnboundedSender<T> {
+    fn drop(&mut self) {
+        drop_sender(&self.chan);
+    }
+}
+
+#[derive(Debug)]
+pub struct UnboundedReceiver<T> {
+    inner: Receiver<T>,
+}
+
+impl <T> UnboundedReceiver<T> {
+    pub fn recv(&mut self) -> RecvFuture<'_, T> {
+        self.inner.recv()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+}
@@ -0,0 +1,164 @@
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use alloc::vec::Vec;
+use crate::{Mutex, MutexRef};
+
+/// Locks both mutexes, always in the same order whatever the argument order,
+/// so two tasks locking the same pair cannot deadlock each other. A fixed
+/// order already rules out the cycle, so there is nothing to gain from
+/// backing off and retrying: the second lock is simply waited for while the
+/// first is held. Panics if both are handles to the same mutex, since the
+/// second lock could never be taken; with `panic_free` it never resolves
+/// instead, without taking either lock.
+pub async fn lock_both<'a, 'b, A, B>(a: &'a Mutex<A>, b: &'b Mutex<B>) -> (MutexRef<'a, A>, MutexRef<'b, B>)
+where A: ?Sized, B: ?Sized {
+    #[cfg(not(feature = "panic_free"))]
+    assert_ne!(a.addr(), b.addr(), "lock_both called with the same mutex twice");
+    #[cfg(feature = "panic_free")]
+    if a.addr() == b.addr() {
+        return core::future::pending().await;
+    }
+    if a.addr() < b.addr() {
+        let a = a.lock().await;
+        (a, b.lock().await)
+    } else {
+        let b = b.lock().await;
+        (a.lock().await, b)
+    }
+}
+
+/// Locks every mutex in a tuple of handles in the same order as `lock_both`,
+/// so two tasks locking overlapping sets cannot deadlock each other. Panics
+/// if the tuple holds the same mutex twice, or never resolves with
+/// `panic_free`. See also `lock_all!`.
+pub trait LockAll<'a> {
+    type Guards;
+
+    fn lock_all(self) -> impl Future<Output = Self::Guards>;
+}
+
+macro_rules! impl_lock_all {
+    ($($ty:ident $mutex:ident $guard:ident $index:tt),+) => {
+        impl <'a, $($ty: ?Sized),+> LockAll<'a> for ($(&'a Mutex<$ty>,)+) {
+            type Guards = ($(MutexRef<'a, $ty>,)+);
+
+            async fn lock_all(self) -> Self::Guards {
+                let ($($mutex,)+) = self;
+                let mut order = [$(($mutex.addr(), $index)),+];
+                order.sort_unstable();
+                let repeated = order.windows(2).any(|pair| matches!(pair, [(a, _), (b, _)] if a == b));
+                #[cfg(not(feature = "panic_free"))]
+                assert!(!repeated, "lock_all called with the same mutex twice");
+                #[cfg(feature = "panic_free")]
+                if repeated {
+                    return core::future::pending().await;
+                }
+
+                $(let mut $guard = None;)+
+                for (_addr, index) in order {
+                    match index {
+                        $($index => $guard = Some($mutex.lock().await),)+
+                        _ => {}
+                    }
+                }
+                // every index was locked above, so only the first arm is taken
+                match ($($guard,)+) {
+                    ($(Some($guard),)+) => ($($guard,)+),
+                    _ => core::future::pending().await,
+                }
+            }
+        }
+    };
+}
+
+impl_lock_all!(A a guard_a 0);
+impl_lock_all!(A a guard_a 0, B b guard_b 1);
+impl_lock_all!(A a guard_a 0, B b guard_b 1, C c guard_c 2);
+impl_lock_all!(A a guard_a 0, B b guard_b 1, C c guard_c 2, D d guard_d 3);
+impl_lock_all!(A a guard_a 0, B b guard_b 1, C c guard_c 2, D d guard_d 3, E e guard_e 4);
+impl_lock_all!(A a guard_a 0, B b guard_b 1, C c guard_c 2, D d guard_d 3, E e guard_e 4, F f guard_f 5);
+impl_lock_all!(A a guard_a 0, B b guard_b 1, C c guard_c 2, D d guard_d 3, E e guard_e 4, F f guard_f 5, G g guard_g 6);
+impl_lock_all!(A a guard_a 0, B b guard_b 1, C c guard_c 2, D d guard_d 3, E e guard_e 4, F f guard_f 5, G g guard_g 6, H h guard_h 7);
+
+/// A set of mutexes that are always locked together, in the same order as
+/// `lock_both`, for a consistent view of all of them at once.
+pub struct MutexGroup<T: ?Sized> {
+    members: Vec<Mutex<T>>,
+    // indices into `members`, sorted by address
+    order: Vec<usize>,
+}
+
+impl <T: ?Sized> Default for MutexGroup<T> {
+    fn default() -> Self {
+        MutexGroup { members: Vec::new(), order: Vec::new() }
+    }
+}
+
+impl <T: ?Sized> MutexGroup<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a member, returning `false` if it already was one.
+    pub fn add(&mut self, mutex: Mutex<T>) -> bool {
+        let members = &self.members;
+        match self.order.binary_search_by_key(&Some(mutex.addr()), |&index| members.get(index).map(Mutex::addr)) {
+            Ok(_) => false,
+            Err(position) => {
+                self.order.insert(position, self.members.len());
+                self.members.push(mutex);
+                true
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+impl <T: ?Sized> MutexGroup<T> {
+    /// Locks every member. The guards are in the order the members were added
+    /// and are all released when the returned guard is dropped.
+    pub async fn lock_group(&self) -> GroupGuard<'_, T> {
+        let mut guards: Vec<_> = self.members.iter().map(|_| None).collect();
+        for &index in &self.order {
+            if let (Some(guard), Some(member)) = (guards.get_mut(index), self.members.get(index)) {
+                *guard = Some(member.lock().await);
+            }
+        }
+        // `order` holds every index once, so no guard is left out
+        GroupGuard { guards: guards.into_iter().flatten().collect() }
+    }
+}
+
+pub struct GroupGuard<'a, T: ?Sized> {
+    guards: Vec<MutexRef<'a, T>>,
+}
+
+impl <'a, T: ?Sized> Deref for GroupGuard<'a, T> {
+    type Target = [MutexRef<'a, T>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.guards
+    }
+}
+
+impl <'a, T: ?Sized> DerefMut for GroupGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guards
+    }
+}
+
+/// Locks up to eight mutexes with `LockAll`, yielding a tuple of guards in
+/// argument order: `let (a, b, c) = lock_all!(a, b, c).await;`
+#[macro_export]
+macro_rules! lock_all {
+    ($($mutex:expr),+ $(,)?) => {
+        $crate::LockAll::lock_all(($({ let mutex: &$crate::Mutex<_> = &$mutex; mutex },)+))
+    };
+}
@@ -0,0 +1,1478 @@
+use core::cell::{Cell, RefCell, UnsafeCell};
+use core::task::{ready, Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use core::ops::{Deref, DerefMut};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use alloc::vec::Vec;
+use alloc::boxed::Box;
+use alloc::string::String;
+use futures_core::{FusedFuture, Stream};
+use crate::{Pointer, WeakPointer, TryLockError, PoisonError, Notify, Fairness, WakeStrategy, MutexBuilder};
+#[cfg(feature = "panic_free")]
+use crate::LockError;
+use crate::waiters::{WakerId, Waiters};
+#[cfg(feature = "stats")]
+use crate::MutexStats;
+#[cfg(any(feature = "stats", feature = "web"))]
+use crate::clock::Instant;
+#[cfg(feature = "registry")]
+use crate::registry::{self, LockInfo, LockSnapshot};
+#[cfg(feature = "watchdog")]
+use crate::watchdog::{Watchdog, HoldTimer, Overdue, warn_long_hold};
+#[cfg(feature = "web-profiling")]
+use crate::profiling::Mark;
+#[cfg(feature = "chrome_trace")]
+use crate::chrome_trace::{self, Began};
+#[cfg(feature = "web")]
+use crate::contention::WaitWarnings;
+#[cfg(feature = "instrumentation")]
+use crate::instrumentation;
+
+// Where a guard was asked for, kept only for diagnostics.
+#[cfg(any(feature = "registry", feature = "watchdog"))]
+type Site = &'static core::panic::Location<'static>;
+#[cfg(not(any(feature = "registry", feature = "watchdog")))]
+#[derive(Clone, Copy)]
+struct Site;
+
+#[cfg(any(feature = "registry", feature = "watchdog"))]
+#[track_caller]
+fn caller() -> Site {
+    core::panic::Location::caller()
+}
+
+#[cfg(not(any(feature = "registry", feature = "watchdog")))]
+fn caller() -> Site {
+    Site
+}
+
+#[derive(Debug, Default)]
+struct MutexState {
+    locked: bool,
+    fairness: Fairness,
+    wake: WakeStrategy,
+    poisonable: bool,
+    poisoned: bool,
+    name: Option<&'static str>,
+    level: Option<u32>,
+    // queued by descending priority
+    waiters: Waiters<u8>,
+    // notified on every release once `MutexRef::wait_until` has been used
+    released: Option<Notify>,
+    // bumped whenever a guard that was mutably dereferenced is dropped
+    version: u64,
+    watchers: Waiters,
+    // open from acquisition until release
+    #[cfg(feature = "tracing")]
+    hold: Option<tracing::Span>,
+    // measured from acquisition to release
+    #[cfg(feature = "web-profiling")]
+    hold_mark: Option<Mark>,
+    #[cfg(feature = "chrome_trace")]
+    traced_hold: Option<Began>,
+    #[cfg(feature = "web")]
+    wait_warnings: WaitWarnings,
+    #[cfg(feature = "stats")]
+    stats: MutexStats,
+    #[cfg(any(feature = "registry", feature = "watchdog"))]
+    holder: Option<Site>,
+    #[cfg(feature = "watchdog")]
+    watchdog: Option<Watchdog>,
+    #[cfg(feature = "watchdog")]
+    hold_timer: HoldTimer,
+    // the `task_scope` that took the lock
+    #[cfg(all(feature = "std", debug_assertions))]
+    holder_task: Option<crate::TaskId>,
+}
+
+impl MutexState {
+    #[cfg(feature = "watchdog")]
+    fn overdue(&mut self, released: bool) -> Option<Overdue> {
+        let watchdog = self.watchdog?;
+        self.hold_timer.check(&watchdog, self.name, self.holder?, released)
+    }
+
+    // whether a released lock is passed straight to the woken waiter
+    fn hands_off(&self) -> bool {
+        self.wake == WakeStrategy::One && self.fairness.hands_off()
+    }
+
+    // Picks the waiter to wake on release. Only policies that hand the lock
+    // over leave it locked for the woken waiter.
+    fn next_waiter(&mut self) -> Option<Waker> {
+        match self.fairness {
+            Fairness::Fifo => self.waiters.grant_front(),
+            Fairness::Lifo => self.waiters.grant_latest(),
+            Fairness::Unfair => {
+                self.locked = false;
+                self.waiters.grant_front()
+            }
+        }
+    }
+}
+
+fn new_state() -> Pointer<RefCell<MutexState>> {
+    let state: Pointer<RefCell<MutexState>> = Default::default();
+    #[cfg(feature = "registry")]
+    registry::register(Pointer::downgrade(&state) as WeakPointer<dyn LockInfo>);
+    state
+}
+
+pub struct Mutex<T: ?Sized> {
+    value: Pointer<UnsafeCell<T>>,
+    state: Pointer<RefCell<MutexState>>,
+}
+
+impl <T: ?Sized> Clone for Mutex<T> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), state: self.state.clone() }
+    }
+}
+
+impl <T: fmt::Debug + ?Sized> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Mutex");
+        if let Some(name) = self.name() {
+            d.field("name", &name);
+        }
+        self.peek(|value| match value {
+            Some(value) => d.field("value", &value),
+            None => d.field("value", &format_args!("<locked, {} waiters>", self.waiter_count())),
+        });
+        d.finish()
+    }
+}
+
+impl <T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self { value: Default::default(), state: new_state() }
+    }
+}
+
+impl Mutex<()> {
+    /// Starts configuring a mutex: `Mutex::builder().fairness(..).build(value)`.
+    pub fn builder() -> MutexBuilder {
+        MutexBuilder::new()
+    }
+}
+
+impl <T> Mutex<T> {
+    pub fn new(value: T) -> Self {
+        Mutex {
+            value: Pointer::new(UnsafeCell::new(value)),
+            state: new_state(),
+        }
+    }
+
+    /// Builds the value with a weak handle to the mutex that will hold it,
+    /// for structures that need to point back at their owner. Upgrading the
+    /// handle inside `f` fails.
+    pub fn new_cyclic(f: impl FnOnce(&WeakMutex<T>) -> T) -> Self {
+        let state = new_state();
+        let value = Pointer::new_cyclic(|value| {
+            let weak = WeakMutex { value: value.clone(), state: Pointer::downgrade(&state) };
+            UnsafeCell::new(f(&weak))
+        });
+        Mutex { value, state }
+    }
+
+    /// Creates a mutex that is poisoned when a guard is dropped during a
+    /// panic, as `std::sync::Mutex` is. Only `lock_result` and
+    /// `try_lock_result` report poisoning; `lock` and `try_lock` ignore it.
+    pub fn new_poisonable(value: T) -> Self {
+        MutexBuilder::new().poisonable(true).build(value)
+    }
+
+    pub(crate) fn with_options(value: T, options: MutexBuilder) -> Self {
+        let mutex = Mutex::new(value);
+        {
+            let mut state = (*mutex.state).borrow_mut();
+            state.fairness = options.fairness;
+            state.wake = options.wake;
+            state.poisonable = options.poisonable;
+            state.name = options.name;
+            state.level = options.level;
+            #[cfg(feature = "web")]
+            {
+                state.wait_warnings.threshold = options.warn_wait_after;
+            }
+            #[cfg(feature = "watchdog")]
+            {
+                state.watchdog = options.warn_after.map(|threshold| Watchdog {
+                    threshold,
+                    handler: options.on_long_hold.unwrap_or(warn_long_hold),
+                });
+            }
+        }
+        mutex
+    }
+
+    /// Recovers the value when this is the last handle to it, otherwise
+    /// hands the handle back.
+    pub fn into_inner(self) -> Result<T, Self> {
+        let Mutex { value, state } = self;
+        match Pointer::try_unwrap(value) {
+            Ok(value) => Ok(value.into_inner()),
+            Err(value) => Err(Mutex { value, state }),
+        }
+    }
+}
+
+impl <T: ?Sized> Mutex<T> {
+    /// Stores an unsized value such as `Box<dyn Trait>` without boxing it
+    /// twice. `Mutex<[T]>` and `Mutex<str>` can be built with `From` as well.
+    pub fn from_box(value: Box<T>) -> Self {
+        let value: Pointer<T> = Pointer::from(value);
+        // SAFETY: `UnsafeCell<T>` has the same layout as `T`, so the pointer
+        // keeps its metadata and still addresses the same allocation.
+        let value = unsafe { Pointer::from_raw(Pointer::into_raw(value) as *const UnsafeCell<T>) };
+        Mutex { value, state: new_state() }
+    }
+
+    /// Borrows the value without locking when this is the only handle to it.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        Pointer::get_mut(&mut self.value).map(UnsafeCell::get_mut)
+    }
+
+    /// Turns the mutex into a plain shared pointer to the value when this is
+    /// the last handle, strong or weak, and the lock is free. Otherwise hands
+    /// the handle back.
+    pub fn freeze(mut self) -> Result<Pointer<T>, Self> {
+        if self.is_locked() || Pointer::get_mut(&mut self.value).is_none() {
+            return Err(self);
+        }
+        // SAFETY: no other handle can reach the `UnsafeCell`, and it has the
+        // same layout as `T`.
+        Ok(unsafe { Pointer::from_raw(Pointer::into_raw(self.value) as *const T) })
+    }
+
+    /// Raw pointer to the value. Dereferencing it is only sound while nothing
+    /// else accesses the value, whether through a guard or not.
+    pub fn data_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
+    /// Borrows the value without taking the lock.
+    ///
+    /// # Safety
+    ///
+    /// No guard, and no other reference obtained from this method or from
+    /// `data_ptr`, may be used while the returned reference is alive.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_unchecked(&self) -> &mut T {
+        &mut *self.value.get()
+    }
+
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn lock(&self) -> LockFuture<'_, T> {
+        self.lock_with_priority(0)
+    }
+
+    /// Like `lock`, but queues ahead of every waiter with a lower priority.
+    /// Waiters with equal priority are served in arrival order, and `lock`
+    /// waits with the lowest priority.
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn lock_with_priority(&self, priority: u8) -> LockFuture<'_, T> {
+        LockFuture {
+            waker_id: None,
+            priority,
+            done: false,
+            mutex: self,
+            site: caller(),
+            #[cfg(feature = "tracing")]
+            wait: None,
+            #[cfg(any(feature = "stats", feature = "web"))]
+            queued_at: None,
+            #[cfg(feature = "web-profiling")]
+            wait_mark: None,
+            #[cfg(feature = "chrome_trace")]
+            traced_wait: None,
+        }
+    }
+
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn lock_owned(&self) -> OwnedLockFuture<T> {
+        OwnedLockFuture {
+            waker_id: None,
+            done: false,
+            mutex: self.clone(),
+            site: caller(),
+            #[cfg(feature = "tracing")]
+            wait: None,
+            #[cfg(any(feature = "stats", feature = "web"))]
+            queued_at: None,
+            #[cfg(feature = "web-profiling")]
+            wait_mark: None,
+            #[cfg(feature = "chrome_trace")]
+            traced_wait: None,
+        }
+    }
+
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn try_lock(&self) -> Option<MutexRef<'_, T>> {
+        if self.acquire(caller()) {
+            Some(MutexRef::new(self))
+        } else {
+            None
+        }
+    }
+
+    /// Like `try_lock`, but reports why the lock could not be taken.
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn try_lock_result(&self) -> Result<MutexRef<'_, T>, TryLockError<MutexRef<'_, T>>> {
+        let guard = self.try_lock().ok_or(TryLockError::WouldBlock)?;
+        Ok(self.check_poison(guard)?)
+    }
+
+    #[cfg_attr(any(feature = "registry", feature = "watchdog"), track_caller)]
+    pub fn try_lock_owned(&self) -> Option<OwnedMutexRef<T>> {
+        if self.acquire(caller()) {
+            Some(OwnedMutexRef::new(self.clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Reports whether the lock is held, including while it is being handed
+    /// to a waiter that has yet to be polled.
+    pub fn is_locked(&self) -> bool {
+        (*self.state).borrow().locked
+    }
+
+    /// Number of futures queued for the lock. A waiter the lock has just been
+    /// handed to no longer counts.
+    pub fn waiter_count(&self) -> usize {
+        (*self.state).borrow().waiters.len()
+    }
+
+    /// The name given to `MutexBuilder::name`, if any.
+    pub fn name(&self) -> Option<&'static str> {
+        (*self.state).borrow().name
+    }
+
+    pub fn has_waiters(&self) -> bool {
+        !(*self.state).borrow().waiters.is_empty()
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> MutexStats {
+        let state = (*self.state).borrow();
+        MutexStats { name: state.name, waiters: state.waiters.len(), ..state.stats }
+    }
+
+    /// Releases the lock as if its guard had been dropped, handing it to the
+    /// next waiter if there is one.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held and the guard holding it must never be used or
+    /// dropped again, e.g. because it was passed to `mem::forget`.
+    pub unsafe fn force_unlock(&self) {
+        release(&self.state);
+    }
+
+    pub fn downgrade(&self) -> WeakMutex<T> {
+        WeakMutex {
+            value: Pointer::downgrade(&self.value),
+            state: Pointer::downgrade(&self.state),
+        }
+    }
+
+    /// Counts the releases of guards that were mutably dereferenced. Wraps
+    /// around on overflow.
+    pub fn version(&self) -> u64 {
+        (*self.state).borrow().version
+    }
+
+    /// Waits until `version()` differs from `since`, and returns the new
+    /// version. Completes right away if it already does.
+    pub fn changed(&self, since: u64) -> Changed<'_, T> {
+        Changed {
+            waker_id: (*self.state).borrow_mut().watchers.next_id(),
+            since,
+            mutex: self,
+        }
+    }
+
+    /// Returns a stream that takes the lock again each time it is polled
+    /// for the next item. It never ends, and keeps its place in the queue
+    /// between polls.
+    pub fn lock_stream(&self) -> LockStream<'_, T> {
+        LockStream { mutex: self, lock: None }
+    }
+
+    /// Returns a stream that yields each time a guard that was mutably
+    /// dereferenced is dropped. Changes made while the stream is not being
+    /// polled are coalesced into a single item.
+    pub fn subscribe(&self) -> Subscription<T> {
+        let mut state = (*self.state).borrow_mut();
+        Subscription {
+            waker_id: state.watchers.next_id(),
+            seen: state.version,
+            mutex: self.clone(),
+        }
+    }
+
+    /// Reports whether both handles share the same value.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Pointer::ptr_eq(&self.state, &other.state)
+    }
+
+    // Reads the value in place when the mutex is unlocked. Unlike
+    // `try_lock`, this is not an acquisition: no stats, hooks or waiters
+    // see it.
+    pub(crate) fn peek<R>(&self, f: impl FnOnce(Option<&T>) -> R) -> R {
+        // held so that the value cannot be locked while `f` runs
+        let state = (*self.state).borrow();
+        if state.locked {
+            f(None)
+        } else {
+            // SAFETY: no guard is alive while the mutex is unlocked, and none
+            // can be taken while the state is borrowed.
+            f(Some(unsafe { &*self.value.get() }))
+        }
+    }
+
+    // number of strong handles, including those held by guards and futures
+    #[cfg(feature = "std")]
+    pub(crate) fn handle_count(&self) -> usize {
+        Pointer::strong_count(&self.state)
+    }
+
+    // stable identity used to order multi-lock acquisition
+    pub(crate) fn addr(&self) -> usize {
+        Pointer::as_ptr(&self.state) as usize
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        (*self.state).borrow().poisoned
+    }
+
+    pub fn clear_poison(&self) {
+        (*self.state).borrow_mut().poisoned = false;
+    }
+
+    fn check_poison<G>(&self, guard: G) -> Result<G, PoisonError<G>> {
+        if self.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+
+    fn acquire(&self, site: Site) -> bool {
+        #[cfg(feature = "instrumentation")]
+        instrumentation::emit(self.name(), self.addr(), |hooks, lock| hooks.on_lock_attempt(lock));
+        let acquired = {
+            let mut state = (*self.state).borrow_mut();
+            // checked even though `try_lock` cannot deadlock, since the lock
+            // it takes still counts towards the order of later ones
+            #[cfg(all(feature = "std", debug_assertions))]
+            if let Some(level) = state.level {
+                crate::task::check_level(level, state.name);
+            }
+            if state.locked {
+                false
+            } else {
+                state.locked = true;
+                self.acquired(&mut state, false, site);
+                true
+            }
+        };
+        #[cfg(feature = "instrumentation")]
+        if acquired {
+            instrumentation::emit(self.name(), self.addr(), |hooks, lock| hooks.on_acquired(lock, false));
+        }
+        acquired
+    }
+
+    // Futures only take a waiter slot once they have to wait, so an
+    // uncontended lock never touches the queue.
+    fn acquire_for(&self, waker_id: Option<WakerId>, site: Site) -> bool {
+        #[cfg(feature = "instrumentation")]
+        if waker_id.is_none() {
+            instrumentation::emit(self.name(), self.addr(), |hooks, lock| hooks.on_lock_attempt(lock));
+        }
+        let mut state = (*self.state).borrow_mut();
+        #[cfg(all(feature = "std", debug_assertions))]
+        if let Some(level) = state.level {
+            crate::task::check_level(level, state.name);
+        }
+        let acquired = if waker_id.is_some_and(|id| state.waiters.take_grant(id)) && state.hands_off() {
+            true
+        } else if state.locked {
+            false
+        } else {
+            state.locked = true;
+            true
+        };
+        if acquired {
+            self.acquired(&mut state, waker_id.is_some(), site);
+        }
+        #[cfg(feature = "instrumentation")]
+        if acquired {
+            let name = state.name;
+            drop(state);
+            instrumentation::emit(name, self.addr(), |hooks, lock| hooks.on_acquired(lock, waker_id.is_some()));
+        }
+        acquired
+    }
+
+    // Runs whenever a guard is about to be handed out, `contended` if the
+    // future taking it had to queue first.
+    #[cfg_attr(not(all(feature = "tracing", feature = "stats", feature = "registry")), allow(unused_variables))]
+    fn acquired(&self, state: &mut MutexState, contended: bool, site: Site) {
+        #[cfg(any(feature = "registry", feature = "watchdog"))]
+        {
+            state.holder = Some(site);
+        }
+        #[cfg(feature = "watchdog")]
+        if state.watchdog.is_some() {
+            state.hold_timer.start();
+        }
+        #[cfg(all(feature = "std", debug_assertions))]
+        {
+            state.holder_task = crate::current_task();
+            if let (Some(level), Some(task)) = (state.level, state.holder_task) {
+                crate::task::hold_level(task, self.addr(), level, state.name);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        {
+            tracing::trace!(mutex = self.addr(), name = state.name, contended, "lock acquired");
+            state.hold = Some(tracing::trace_span!("lock held", mutex = self.addr(), name = state.name));
+        }
+        #[cfg(feature = "web-profiling")]
+        {
+            state.hold_mark = Some(Mark::start(state.name, self.addr(), "lock held"));
+        }
+        #[cfg(feature = "chrome_trace")]
+        {
+            state.traced_hold = chrome_trace::begin();
+        }
+        #[cfg(feature = "stats")]
+        {
+            state.stats.acquisitions += 1;
+            state.stats.contended += u64::from(contended);
+        }
+    }
+
+    #[cfg(feature = "web")]
+    fn check_wait(&self, queued_at: Instant) {
+        let state = &mut *(*self.state).borrow_mut();
+        state.wait_warnings.check(queued_at, state.name, self.addr());
+    }
+
+    #[cfg(feature = "web-profiling")]
+    fn mark(&self, kind: &str) -> Mark {
+        Mark::start((*self.state).borrow().name, self.addr(), kind)
+    }
+
+    #[cfg(feature = "tracing")]
+    fn wait_span(&self) -> tracing::Span {
+        let state = (*self.state).borrow();
+        tracing::trace_span!("lock wait", mutex = self.addr(), name = state.name, waiters = state.waiters.len())
+    }
+
+    fn register(&self, waker_id: &mut Option<WakerId>, waker: &Waker, priority: u8) {
+        #[cfg(feature = "instrumentation")]
+        if waker_id.is_none() {
+            instrumentation::emit(self.name(), self.addr(), |hooks, lock| hooks.on_contended(lock));
+        }
+        let mut state = (*self.state).borrow_mut();
+        #[cfg(all(feature = "std", debug_assertions))]
+        crate::task::check_not_holding(state.holder_task, state.name);
+        let waker_id = *waker_id.get_or_insert_with(|| state.waiters.next_id());
+        state.waiters.register_ordered(waker_id, waker, priority);
+        #[cfg(feature = "stats")]
+        {
+            state.stats.max_waiters = state.stats.max_waiters.max(state.waiters.len());
+        }
+        // a guard can be stuck for good, so report it without waiting for
+        // its release once somebody is kept waiting on it
+        #[cfg(feature = "watchdog")]
+        if let Some(overdue) = state.overdue(false) {
+            drop(state);
+            overdue.report();
+        }
+    }
+
+    fn cancel(&self, waker_id: WakerId) {
+        let (granted, hands_off) = {
+            let mut state = (*self.state).borrow_mut();
+            (state.waiters.cancel(waker_id), state.hands_off())
+        };
+        if !granted {
+            return;
+        }
+
+        if hands_off {
+            // the lock was already handed to this future, so pass it on
+            hand_off(&self.state);
+        } else {
+            // pass on the wake-up this future never acted on
+            let waker = {
+                let mut state = (*self.state).borrow_mut();
+                if state.locked { None } else { state.waiters.grant_front() }
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "panic_free")]
+impl <T: ?Sized> Mutex<T> {
+    /// Like `lock`, but resolves to an error where `lock` would panic: when
+    /// polled after completion, or when the mutex's bookkeeping is already
+    /// borrowed by a call further up the stack.
+    pub fn lock_checked(&self) -> CheckedLockFuture<'_, T> {
+        CheckedLockFuture { lock: self.lock() }
+    }
+}
+
+impl <T: ?Sized> Mutex<T> {
+    /// Like `lock`, but fails with the guard if the mutex is poisoned.
+    pub async fn lock_result(&self) -> Result<MutexRef<'_, T>, PoisonError<MutexRef<'_, T>>> {
+        let guard = self.lock().await;
+        self.check_poison(guard)
+    }
+
+    /// Runs `f` with the lock held and releases it before returning, so the
+    /// guard cannot be kept across unrelated awaits by accident.
+    pub async fn with_lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut *self.lock().await)
+    }
+
+    /// Like `with_lock`, but `f` may await while holding the lock.
+    pub async fn with_lock_async<R>(&self, f: impl AsyncFnOnce(&mut T) -> R) -> R {
+        f(&mut *self.lock().await).await
+    }
+
+    pub async fn update(&self, f: impl FnOnce(&mut T)) {
+        f(&mut *self.lock().await);
+    }
+}
+
+impl <T: Clone> Mutex<T> {
+    /// Creates an independent mutex holding a copy of the value. Unlike
+    /// `clone`, which returns another handle to the same value, changes to
+    /// one are not seen by the other.
+    pub async fn clone_value(&self) -> Mutex<T> {
+        let value = self.lock().await.clone();
+        Mutex::new(value)
+    }
+
+    /// Like `update`, but returns the value from before `f` ran.
+    pub async fn fetch_update(&self, f: impl FnOnce(&mut T)) -> T {
+        let mut guard = self.lock().await;
+        let previous = guard.clone();
+        f(&mut guard);
+        previous
+    }
+
+    pub async fn get_cloned(&self) -> T {
+        self.lock().await.clone()
+    }
+
+    /// Replaces the value, returning the old one.
+    pub async fn set(&self, value: T) -> T {
+        core::mem::replace(&mut *self.lock().await, value)
+    }
+}
+
+impl <T> Mutex<Option<T>> {
+    pub async fn take(&self) -> Option<T> {
+        self.lock().await.take()
+    }
+
+    pub async fn replace(&self, value: T) -> Option<T> {
+        self.lock().await.replace(value)
+    }
+
+    /// Returns a guard to the value, running `f` first if there is none. The
+    /// lock is held while `f` runs, so concurrent callers wait for it rather
+    /// than each inserting their own value.
+    pub async fn get_or_insert_with<F, Fut>(&self, f: F) -> MappedMutexRef<'_, T>
+    where F: FnOnce() -> Fut, Fut: Future<Output = T> {
+        let mut guard = self.lock().await;
+        let value = match guard.take() {
+            Some(value) => value,
+            None => f().await,
+        };
+        MutexRef::map(guard, |slot| slot.insert(value))
+    }
+}
+
+#[cfg(feature = "registry")]
+impl LockInfo for RefCell<MutexState> {
+    fn describe(&self, out: &mut String) {
+        use core::fmt::Write;
+        let Ok(state) = self.try_borrow() else {
+            let _ = write!(out, "Mutex@{:p}: busy", self);
+            return;
+        };
+        let _ = match state.name {
+            Some(name) => write!(out, "{}: ", name),
+            None => write!(out, "Mutex@{:p}: ", self),
+        };
+        let _ = match (state.locked, state.holder) {
+            (false, _) => write!(out, "free"),
+            (true, Some(site)) => write!(out, "held since {}", site),
+            (true, None) => write!(out, "held, being handed to a waiter"),
+        };
+        let _ = write!(out, ", {} waiting", state.waiters.len());
+    }
+
+    fn snapshot(&self) -> Option<LockSnapshot> {
+        let state = self.try_borrow().ok()?;
+        Some(LockSnapshot {
+            name: state.name,
+            address: self as *const RefCell<MutexState> as usize,
+            held: state.locked,
+            holder: state.holder,
+            waiters: state.waiters.len(),
+        })
+    }
+}
+
+// Called when a guard that handed out `&mut T` gives the lock up.
+fn mark_changed(state: &RefCell<MutexState>) {
+    let wakers: Vec<_> = {
+        let mut state = state.borrow_mut();
+        state.version = state.version.wrapping_add(1);
+        state.watchers.drain().collect()
+    };
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+fn release(state: &RefCell<MutexState>) {
+    #[cfg(any(feature = "tracing", feature = "chrome_trace", feature = "instrumentation", all(feature = "std", debug_assertions)))]
+    let id = state as *const RefCell<MutexState> as usize;
+    // Unless the mutex is unfair, hand the lock straight to the next waiter
+    // so that a newcomer cannot barge in before the waiter's next poll.
+    #[cfg(feature = "watchdog")]
+    let overdue;
+    #[cfg(feature = "instrumentation")]
+    let name;
+    let (waker, racers, released) = {
+        let mut state = state.borrow_mut();
+        #[cfg(feature = "instrumentation")]
+        {
+            name = state.name;
+        }
+        #[cfg(feature = "watchdog")]
+        {
+            overdue = state.overdue(true);
+        }
+        #[cfg(feature = "tracing")]
+        {
+            tracing::trace!(mutex = id, name = state.name, waiters = state.waiters.len(), "lock released");
+            state.hold = None;
+        }
+        #[cfg(feature = "web-profiling")]
+        {
+            state.hold_mark = None;
+        }
+        #[cfg(feature = "chrome_trace")]
+        if let Some(began) = state.traced_hold.take() {
+            chrome_trace::end(began, state.name, id, "lock held");
+        }
+        #[cfg(any(feature = "registry", feature = "watchdog"))]
+        {
+            state.holder = None;
+        }
+        #[cfg(all(feature = "std", debug_assertions))]
+        if let Some(task) = state.holder_task.take() {
+            if state.level.is_some() {
+                crate::task::release_level(task, id);
+            }
+        }
+        let (waker, racers) = match state.wake {
+            WakeStrategy::One => (state.next_waiter(), Vec::new()),
+            WakeStrategy::All => (None, state.waiters.drain().collect()),
+        };
+        if waker.is_none() {
+            state.locked = false;
+        }
+        (waker, racers, state.released.clone())
+    };
+
+    #[cfg(feature = "watchdog")]
+    if let Some(overdue) = overdue {
+        overdue.report();
+    }
+    #[cfg(feature = "instrumentation")]
+    instrumentation::emit(name, id, |hooks, lock| hooks.on_released(lock));
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+    racers.into_iter().for_each(Waker::wake);
+    if let Some(released) = released {
+        released.notify_waiters();
+    }
+}
+
+// Passes on a lock that was handed to a future which went away before taking
+// it. Nothing held the lock in between, so there is no hold to end and no
+// release to report.
+fn hand_off(state: &RefCell<MutexState>) {
+    let waker = {
+        let mut state = state.borrow_mut();
+        let waker = state.next_waiter();
+        if waker.is_none() {
+            state.locked = false;
+        }
+        waker
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+// Run by every guard when it gives the lock up.
+fn unlock(state: &RefCell<MutexState>, dirty: bool) {
+    // without std there is no unwinding to detect, so nothing is poisoned
+    #[cfg(feature = "std")]
+    if std::thread::panicking() {
+        let mut state = state.borrow_mut();
+        state.poisoned |= state.poisonable;
+    }
+    if dirty {
+        mark_changed(state);
+    }
+    release(state);
+}
+
+pub struct MutexRef<'a, T: ?Sized> {
+    pub(crate) mutex: &'a Mutex<T>,
+    // false while `unlocked` has given the lock up
+    held: Cell<bool>,
+    dirty: bool,
+}
+
+impl <'a, T: ?Sized> MutexRef<'a, T> {
+    fn new(mutex: &'a Mutex<T>) -> Self {
+        MutexRef {
+            mutex,
+            held: Cell::new(true),
+            dirty: false,
+        }
+    }
+
+    fn value_ptr(&self) -> *mut T {
+        // a cancelled `unlocked` leaves the guard without the lock
+        #[cfg(not(feature = "panic_free"))]
+        assert!(self.held.get(), "MutexRef used after its unlocked() future was dropped");
+        // a deref has no error to return, so the lock is taken back if it is
+        // free and there is no sound way on if it is not
+        #[cfg(feature = "panic_free")]
+        if !self.held.get() {
+            if !self.mutex.acquire(caller()) {
+                crate::abort();
+            }
+            self.held.set(true);
+        }
+        self.mutex.value.get()
+    }
+
+    /// Projects the guard onto a part of the locked value, keeping the lock
+    /// held until the returned guard is dropped.
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&mut T) -> &mut U) -> MappedMutexRef<'a, U> {
+        // SAFETY: `this` is forgotten below and the mapped guard releases the
+        // lock in its place.
+        let value = unsafe { &mut *this.value_ptr() };
+        let state = &this.mutex.state;
+        core::mem::forget(this);
+        MappedMutexRef {
+            value: f(value),
+            state,
+        }
+    }
+
+    /// Like `map`, but hands the original guard back if `f` returns `None`.
+    pub fn try_map<U: ?Sized>(this: Self, f: impl FnOnce(&mut T) -> Option<&mut U>) -> Result<MappedMutexRef<'a, U>, Self> {
+        // SAFETY: see `map`; on failure no reference outlives the call to `f`.
+        let value = unsafe { &mut *this.value_ptr() };
+        match f(value) {
+            Some(value) => {
+                let state = &this.mutex.state;
+                core::mem::forget(this);
+                Ok(MappedMutexRef { value, state })
+            }
+            None => Err(this),
+        }
+    }
+
+    /// Keeps the lock held forever and returns the value for as long as the
+    /// mutex is borrowed.
+    pub fn leak(this: Self) -> &'a mut T {
+        // SAFETY: the guard is forgotten, so the lock is never released and
+        // no other reference to the value can be handed out.
+        let value = unsafe { &mut *this.value_ptr() };
+        core::mem::forget(this);
+        value
+    }
+}
+
+impl <'a, T: ?Sized> MutexRef<'a, T> {
+    /// Releases the lock while `f` runs and takes it back before returning.
+    /// If this future is dropped early the guard is left without the lock:
+    /// dereferencing it panics and dropping it does nothing. With
+    /// `panic_free`, dereferencing it takes the lock back if it is free and
+    /// aborts if it is not.
+    pub async fn unlocked<R>(&mut self, f: impl Future<Output = R>) -> R {
+        self.held.set(false);
+        if core::mem::take(&mut self.dirty) {
+            mark_changed(&self.mutex.state);
+        }
+        release(&self.mutex.state);
+        let output = f.await;
+
+        let relocked = self.mutex.lock().await;
+        relocked.held.set(false);
+        self.held.set(true);
+        output
+    }
+
+    /// Releases the lock until another holder releases it with `pred`
+    /// satisfied, then returns with the lock held again. Returns right away
+    /// if `pred` already holds.
+    pub async fn wait_until(mut this: Self, mut pred: impl FnMut(&T) -> bool) -> Self {
+        while !pred(&this) {
+            let mutex = this.mutex;
+            let released = (*mutex.state).borrow_mut().released.get_or_insert_with(Notify::new).clone();
+            drop(this);
+            released.notified().await;
+            this = mutex.lock().await;
+        }
+        this
+    }
+
+    /// Like `wait_until`, but waits for as long as `pred` holds.
+    pub async fn wait_while(this: Self, mut pred: impl FnMut(&T) -> bool) -> Self {
+        Self::wait_until(this, |value| !pred(value)).await
+    }
+}
+
+impl <'a, T: ?Sized> Deref for MutexRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &*self.value_ptr() }
+    }
+}
+
+impl <'a, T: ?Sized> DerefMut for MutexRef<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.dirty = true;
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &mut *self.value_ptr() }
+    }
+}
+
+impl <'a, T: fmt::Debug + ?Sized> fmt::Debug for MutexRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl <'a, T: ?Sized> Drop for MutexRef<'a, T> {
+    fn drop(&mut self) {
+        if self.held.get() {
+            unlock(&self.mutex.state, self.dirty);
+        }
+    }
+}
+
+pub struct MappedMutexRef<'a, T: ?Sized> {
+    value: &'a mut T,
+    state: &'a RefCell<MutexState>,
+}
+
+impl <'a, T: ?Sized> MappedMutexRef<'a, T> {
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&mut T) -> &mut U) -> MappedMutexRef<'a, U> {
+        let state = this.state;
+        // SAFETY: `this` is forgotten below, so the reference is the only
+        // access to the value for as long as the lock is held.
+        let value = unsafe { &mut *(this.value as *mut T) };
+        core::mem::forget(this);
+        MappedMutexRef {
+            value: f(value),
+            state,
+        }
+    }
+
+    pub fn try_map<U: ?Sized>(this: Self, f: impl FnOnce(&mut T) -> Option<&mut U>) -> Result<MappedMutexRef<'a, U>, Self> {
+        // SAFETY: see `map`; on failure no reference outlives the call to `f`.
+        let value = unsafe { &mut *(this.value as *mut T) };
+        match f(value) {
+            Some(value) => {
+                let state = this.state;
+                core::mem::forget(this);
+                Ok(MappedMutexRef { value, state })
+            }
+            None => Err(this),
+        }
+    }
+}
+
+impl <'a, T: ?Sized> Deref for MappedMutexRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl <'a, T: ?Sized> DerefMut for MappedMutexRef<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
+impl <'a, T: fmt::Debug + ?Sized> fmt::Debug for MappedMutexRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl <'a, T: ?Sized> Drop for MappedMutexRef<'a, T> {
+    fn drop(&mut self) {
+        // mapping already handed out `&mut`, so count it as a change
+        unlock(self.state, true);
+    }
+}
+
+pub struct OwnedMutexRef<T: ?Sized> {
+    mutex: Mutex<T>,
+    dirty: bool,
+}
+
+impl <T: ?Sized> OwnedMutexRef<T> {
+    fn new(mutex: Mutex<T>) -> Self {
+        OwnedMutexRef { mutex, dirty: false }
+    }
+}
+
+impl <T: ?Sized + 'static> OwnedMutexRef<T> {
+    /// Keeps the lock held forever. The guard's handle is leaked along with
+    /// it, so the value is never dropped.
+    pub fn leak(this: Self) -> &'static mut T {
+        // SAFETY: see `MutexRef::leak`; the forgotten handle also keeps the
+        // allocation alive for the rest of the program.
+        let value = unsafe { &mut *this.mutex.value.get() };
+        core::mem::forget(this);
+        value
+    }
+}
+
+impl <T: ?Sized> Deref for OwnedMutexRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl <T: ?Sized> DerefMut for OwnedMutexRef<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.dirty = true;
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+impl <T: fmt::Debug + ?Sized> fmt::Debug for OwnedMutexRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl <T: ?Sized> Drop for OwnedMutexRef<T> {
+    fn drop(&mut self) {
+        unlock(&self.mutex.state, self.dirty);
+    }
+}
+
+/// Resolves to a guard once the lock is taken. Polling it again after that
+/// panics, or stays pending with `panic_free`; `is_terminated` reports when
+/// it has completed.
+pub struct LockFuture<'a, T: ?Sized> {
+    waker_id: Option<WakerId>,
+    priority: u8,
+    done: bool,
+    mutex: &'a Mutex<T>,
+    site: Site,
+    // open while the future is queued
+    #[cfg(feature = "tracing")]
+    wait: Option<tracing::Span>,
+    #[cfg(any(feature = "stats", feature = "web"))]
+    queued_at: Option<Instant>,
+    #[cfg(feature = "web-profiling")]
+    wait_mark: Option<Mark>,
+    #[cfg(feature = "chrome_trace")]
+    traced_wait: Option<Began>,
+}
+
+// nothing is structurally pinned, whatever `T` is
+impl <'a, T: ?Sized> Unpin for LockFuture<'a, T> {}
+
+impl <'a, T: ?Sized> Future for LockFuture<'a, T> {
+    type Output = MutexRef<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        #[cfg(not(feature = "panic_free"))]
+        assert!(!this.done, "LockFuture polled after completion");
+        #[cfg(feature = "panic_free")]
+        if this.done {
+            return Poll::Pending;
+        }
+        if this.mutex.acquire_for(this.waker_id, this.site) {
+            this.done = true;
+            #[cfg(feature = "tracing")]
+            {
+                this.wait = None;
+            }
+            #[cfg(feature = "web-profiling")]
+            {
+                this.wait_mark = None;
+            }
+            #[cfg(feature = "chrome_trace")]
+            if let Some(began) = this.traced_wait {
+                chrome_trace::end(began, this.mutex.name(), this.mutex.addr(), "lock wait");
+            }
+            #[cfg(feature = "stats")]
+            if let Some(queued_at) = this.queued_at {
+                (*this.mutex.state).borrow_mut().stats.wait_time += queued_at.elapsed();
+            }
+            #[cfg(feature = "web")]
+            if let Some(queued_at) = this.queued_at {
+                this.mutex.check_wait(queued_at);
+            }
+            Poll::Ready(MutexRef::new(this.mutex))
+        } else {
+            this.mutex.register(&mut this.waker_id, cx.waker(), this.priority);
+            #[cfg(feature = "tracing")]
+            if this.wait.is_none() {
+                this.wait = Some(this.mutex.wait_span());
+            }
+            #[cfg(feature = "web")]
+            if let Some(queued_at) = this.queued_at {
+                this.mutex.check_wait(queued_at);
+            }
+            #[cfg(any(feature = "stats", feature = "web"))]
+            if this.queued_at.is_none() {
+                this.queued_at = Some(Instant::now());
+            }
+            #[cfg(feature = "web-profiling")]
+            if this.wait_mark.is_none() {
+                this.wait_mark = Some(this.mutex.mark("lock wait"));
+            }
+            #[cfg(feature = "chrome_trace")]
+            if this.traced_wait.is_none() {
+                this.traced_wait = chrome_trace::begin();
+            }
+            Poll::Pending
+        }
+    }
+}
+
+impl <'a, T: ?Sized> FusedFuture for LockFuture<'a, T> {
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
+
+impl <'a, T: ?Sized> Drop for LockFuture<'a, T> {
+    fn drop(&mut self) {
+        if let Some(waker_id) = self.waker_id {
+            self.mutex.cancel(waker_id);
+        }
+    }
+}
+
+#[cfg(feature = "panic_free")]
+pub struct CheckedLockFuture<'a, T: ?Sized> {
+    lock: LockFuture<'a, T>,
+}
+
+#[cfg(feature = "panic_free")]
+impl <'a, T: ?Sized> Future for CheckedLockFuture<'a, T> {
+    type Output = Result<MutexRef<'a, T>, LockError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = &mut self.get_mut().lock;
+        if lock.done {
+            return Poll::Ready(Err(LockError::Completed));
+        }
+        if lock.mutex.state.try_borrow_mut().is_err() {
+            return Poll::Ready(Err(LockError::Inconsistent));
+        }
+        Pin::new(lock).poll(cx).map(Ok)
+    }
+}
+
+#[cfg(feature = "panic_free")]
+impl <'a, T: ?Sized> FusedFuture for CheckedLockFuture<'a, T> {
+    fn is_terminated(&self) -> bool {
+        self.lock.done
+    }
+}
+
+/// Like `LockFuture`, but resolves to an owned guard.
+pub struct OwnedLockFuture<T: ?Sized> {
+    waker_id: Option<WakerId>,
+    done: bool,
+    mutex: Mutex<T>,
+    site: Site,
+    #[cfg(feature = "tracing")]
+    wait: Option<tracing::Span>,
+    #[cfg(any(feature = "stats", feature = "web"))]
+    queued_at: Option<Instant>,
+    #[cfg(feature = "web-profiling")]
+    wait_mark: Option<Mark>,
+    #[cfg(feature = "chrome_trace")]
+    traced_wait: Option<Began>,
+}
+
+impl <T: ?Sized> Unpin for OwnedLockFuture<T> {}
+
+impl <T: ?Sized> Future for OwnedLockFuture<T> {
+    type Output = OwnedMutexRef<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        #[cfg(not(feature = "panic_free"))]
+        assert!(!this.done, "OwnedLockFuture polled after completion");
+        #[cfg(feature = "panic_free")]
+        if this.done {
+            return Poll::Pending;
+        }
+        if this.mutex.acquire_for(this.waker_id, this.site) {
+            this.done = true;
+            #[cfg(feature = "tracing")]
+            {
+                this.wait = None;
+            }
+            #[cfg(feature = "web-profiling")]
+            {
+                this.wait_mark = None;
+            }
+            #[cfg(feature = "chrome_trace")]
+            if let Some(began) = this.traced_wait {
+                chrome_trace::end(began, this.mutex.name(), this.mutex.addr(), "lock wait");
+            }
+            #[cfg(feature = "stats")]
+            if let Some(queued_at) = this.queued_at {
+                (*this.mutex.state).borrow_mut().stats.wait_time += queued_at.elapsed();
+            }
+            #[cfg(feature = "web")]
+            if let Some(queued_at) = this.queued_at {
+                this.mutex.check_wait(queued_at);
+            }
+            Poll::Ready(OwnedMutexRef::new(this.mutex.clone()))
+        } else {
+            this.mutex.register(&mut this.waker_id, cx.waker(), 0);
+            #[cfg(feature = "tracing")]
+            if this.wait.is_none() {
+                this.wait = Some(this.mutex.wait_span());
+            }
+            #[cfg(feature = "web")]
+            if let Some(queued_at) = this.queued_at {
+                this.mutex.check_wait(queued_at);
+            }
+            #[cfg(any(feature = "stats", feature = "web"))]
+            if this.queued_at.is_none() {
+                this.queued_at = Some(Instant::now());
+            }
+            #[cfg(feature = "web-profiling")]
+            if this.wait_mark.is_none() {
+                this.wait_mark = Some(this.mutex.mark("lock wait"));
+            }
+            #[cfg(feature = "chrome_trace")]
+            if this.traced_wait.is_none() {
+                this.traced_wait = chrome_trace::begin();
+            }
+            Poll::Pending
+        }
+    }
+}
+
+impl <T: ?Sized> FusedFuture for OwnedLockFuture<T> {
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
+
+impl <T: ?Sized> Drop for OwnedLockFuture<T> {
+    fn drop(&mut self) {
+        if let Some(waker_id) = self.waker_id {
+            self.mutex.cancel(waker_id);
+        }
+    }
+}
+pub struct Changed<'a, T: ?Sized> {
+    waker_id: WakerId,
+    since: u64,
+    mutex: &'a Mutex<T>,
+}
+
+impl <'a, T: ?Sized> Future for Changed<'a, T> {
+    type Output = u64;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = (*self.mutex.state).borrow_mut();
+        if state.version == self.since {
+            state.watchers.register(self.waker_id, cx.waker(), ());
+            Poll::Pending
+        } else {
+            Poll::Ready(state.version)
+        }
+    }
+}
+
+impl <'a, T: ?Sized> Drop for Changed<'a, T> {
+    fn drop(&mut self) {
+        (*self.mutex.state).borrow_mut().watchers.cancel(self.waker_id);
+    }
+}
+
+pub struct Subscription<T: ?Sized> {
+    waker_id: WakerId,
+    seen: u64,
+    mutex: Mutex<T>,
+}
+
+impl <T: ?Sized> Stream for Subscription<T> {
+    type Item = ();
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let version = {
+            let mut state = (*self.mutex.state).borrow_mut();
+            if state.version == self.seen {
+                state.watchers.register(self.waker_id, cx.waker(), ());
+                return Poll::Pending;
+            }
+            state.version
+        };
+        self.seen = version;
+        Poll::Ready(Some(()))
+    }
+}
+
+impl <T: ?Sized> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        (*self.mutex.state).borrow_mut().watchers.cancel(self.waker_id);
+    }
+}
+
+pub struct LockStream<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    lock: Option<LockFuture<'a, T>>,
+}
+
+impl <'a, T: ?Sized> Stream for LockStream<'a, T> {
+    type Item = MutexRef<'a, T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let lock = this.lock.get_or_insert_with(|| this.mutex.lock());
+        let guard = ready!(Pin::new(lock).poll(cx));
+        this.lock = None;
+        Poll::Ready(Some(guard))
+    }
+}
+
+/// A handle that does not keep the value alive; see `Mutex::downgrade`.
+pub struct WeakMutex<T: ?Sized> {
+    value: WeakPointer<UnsafeCell<T>>,
+    state: WeakPointer<RefCell<MutexState>>,
+}
+
+impl <T: ?Sized> WeakMutex<T> {
+    /// Returns a handle to the mutex unless every strong handle is gone.
+    pub fn upgrade(&self) -> Option<Mutex<T>> {
+        Some(Mutex { value: self.value.upgrade()?, state: self.state.upgrade()? })
+    }
+}
+
+impl <T: ?Sized> Clone for WeakMutex<T> {
+    fn clone(&self) -> Self {
+        WeakMutex { value: self.value.clone(), state: self.state.clone() }
+    }
+}
+
+impl <T: ?Sized> fmt::Debug for WeakMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(WeakMutex)")
+    }
+}
+
+/// Compares and hashes a `Mutex` by handle identity rather than by value, so
+/// handles can be used as `HashMap` or `HashSet` keys.
+pub struct ByHandle<T: ?Sized>(pub Mutex<T>);
+
+impl <T: ?Sized> Clone for ByHandle<T> {
+    fn clone(&self) -> Self {
+        ByHandle(self.0.clone())
+    }
+}
+
+impl <T: fmt::Debug + ?Sized> fmt::Debug for ByHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ByHandle").field(&self.0).finish()
+    }
+}
+
+impl <T: ?Sized> PartialEq for ByHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+}
+
+impl <T: ?Sized> Eq for ByHandle<T> {}
+
+impl <T: ?Sized> Hash for ByHandle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Pointer::as_ptr(&self.0.state).hash(state);
+    }
+}
+
+impl <T: ?Sized> Deref for ByHandle<T> {
+    type Target = Mutex<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl <T: ?Sized> From<Box<T>> for Mutex<T> {
+    fn from(value: Box<T>) -> Self {
+        Mutex::from_box(value)
+    }
+}
+
+impl <T> From<Vec<T>> for Mutex<[T]> {
+    fn from(value: Vec<T>) -> Self {
+        Mutex::from_box(value.into_boxed_slice())
+    }
+}
+
+impl From<String> for Mutex<str> {
+    fn from(value: String) -> Self {
+        Mutex::from_box(value.into_boxed_str())
+    }
+}
+
+impl From<&str> for Mutex<str> {
+    fn from(value: &str) -> Self {
+        Mutex::from_box(value.into())
+    }
+}
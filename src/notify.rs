@@ -0,0 +1,94 @@
+use core::cell::RefCell;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug, Default)]
+struct NotifyState {
+    permit: bool,
+    generation: usize,
+    waiters: Waiters,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Notify {
+    state: Pointer<RefCell<NotifyState>>,
+}
+
+impl Notify {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn notified(&self) -> Notified<'_> {
+        let mut state = (*self.state).borrow_mut();
+        Notified {
+            waker_id: state.waiters.next_id(),
+            generation: state.generation,
+            notify: self,
+        }
+    }
+
+    /// Wakes the oldest waiter, or stores a permit for the next call to
+    /// `notified()` if nobody is waiting.
+    pub fn notify_one(&self) {
+        let waker = {
+            let mut state = (*self.state).borrow_mut();
+            let waker = state.waiters.grant_front();
+            if waker.is_none() {
+                state.permit = true;
+            }
+            waker
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Wakes every `Notified` future created before this call, without
+    /// storing a permit.
+    pub fn notify_waiters(&self) {
+        let wakers: Vec<_> = {
+            let mut state = (*self.state).borrow_mut();
+            state.generation = state.generation.wrapping_add(1);
+            state.waiters.drain().collect()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+pub struct Notified<'a> {
+    waker_id: WakerId,
+    generation: usize,
+    notify: &'a Notify,
+}
+
+impl <'a> Future for Notified<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = (*self.notify.state).borrow_mut();
+        if state.waiters.take_grant(self.waker_id) || state.generation != self.generation {
+            Poll::Ready(())
+        } else if state.permit {
+            state.permit = false;
+            Poll::Ready(())
+        } else {
+            state.waiters.register(self.waker_id, cx.waker(), ());
+            Poll::Pending
+        }
+    }
+}
+
+impl <'a> Drop for Notified<'a> {
+    fn drop(&mut self) {
+        let granted = (*self.notify.state).borrow_mut().waiters.cancel(self.waker_id);
+        // pass on a notification this future received but never observed
+        if granted {
+            self.notify.notify_one();
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use core::cell::RefCell;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug, Default)]
+struct OnceCellState {
+    initializing: bool,
+    generation: usize,
+    waiters: Waiters,
+}
+
+impl OnceCellState {
+    fn changed(&mut self) -> Vec<Waker> {
+        self.generation = self.generation.wrapping_add(1);
+        self.waiters.drain().collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct OnceCell<T> {
+    value: Pointer<core::cell::OnceCell<T>>,
+    state: Pointer<RefCell<OnceCellState>>,
+}
+
+impl <T> Clone for OnceCell<T> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), state: self.state.clone() }
+    }
+}
+
+impl <T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self { value: Default::default(), state: Default::default() }
+    }
+}
+
+impl <T> OnceCell<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Pointer::ptr_eq(&self.state, &other.state)
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.value.set(value)?;
+        let wakers = (*self.state).borrow_mut().changed();
+        wakers.into_iter().for_each(Waker::wake);
+        Ok(())
+    }
+
+    pub async fn wait(&self) -> &T {
+        loop {
+            if let Some(value) = self.value.get() {
+                return value;
+            }
+            self.changed().await;
+        }
+    }
+
+    /// Only one caller runs its initializer at a time; everyone else waits for
+    /// it to finish. If the running initializer is cancelled, one of the
+    /// waiting callers takes over with its own initializer.
+    pub async fn get_or_init<F, Fut>(&self, f: F) -> &T
+    where F: FnOnce() -> Fut, Fut: Future<Output = T> {
+        loop {
+            if let Some(value) = self.value.get() {
+                return value;
+            }
+
+            let initializing = {
+                let mut state = (*self.state).borrow_mut();
+                core::mem::replace(&mut state.initializing, true)
+            };
+            if initializing {
+                self.changed().await;
+                continue;
+            }
+
+            let _initializing = Initializing { cell: self };
+            let value = f().await;
+            // a concurrent `set` may have won the race, in which case its
+            // value is kept and ours is dropped
+            return self.value.get_or_init(|| value);
+        }
+    }
+
+    fn changed(&self) -> Changed<'_, T> {
+        let mut state = (*self.state).borrow_mut();
+        Changed {
+            waker_id: state.waiters.next_id(),
+            generation: state.generation,
+            cell: self,
+        }
+    }
+}
+
+struct Initializing<'a, T> {
+    cell: &'a OnceCell<T>,
+}
+
+impl <'a, T> Drop for Initializing<'a, T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = (*self.cell.state).borrow_mut();
+            state.initializing = false;
+            state.changed()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+struct Changed<'a, T> {
+    waker_id: WakerId,
+    generation: usize,
+    cell: &'a OnceCell<T>,
+}
+
+impl <'a, T> Future for Changed<'a, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = (*self.cell.state).borrow_mut();
+        if state.generation != self.generation {
+            Poll::Ready(())
+        } else {
+            state.waiters.register(self.waker_id, cx.waker(), ());
+            Poll::Pending
+        }
+    }
+}
+
+impl <'a, T> Drop for Changed<'a, T> {
+    fn drop(&mut self) {
+        (*self.cell.state).borrow_mut().waiters.cancel(self.waker_id);
+    }
+}
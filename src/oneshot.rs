@@ -0,0 +1,135 @@
+use core::cell::RefCell;
+use core::error::Error;
+use core::fmt;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug)]
+struct OneshotState<T> {
+    value: Option<T>,
+    sender_dropped: bool,
+    receiver_dropped: bool,
+    waiters: Waiters,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl Error for RecvError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Closed,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "channel empty"),
+            TryRecvError::Closed => write!(f, "channel closed"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let mut waiters = Waiters::default();
+    let waker_id = waiters.next_id();
+    let state = Pointer::new(RefCell::new(OneshotState {
+        value: None,
+        sender_dropped: false,
+        receiver_dropped: false,
+        waiters,
+    }));
+    (Sender { state: state.clone() }, Receiver { state, waker_id })
+}
+
+#[derive(Debug)]
+pub struct Sender<T> {
+    state: Pointer<RefCell<OneshotState<T>>>,
+}
+
+impl <T> Sender<T> {
+    /// Hands the value back if the receiver has already gone away.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let mut state = (*self.state).borrow_mut();
+        if state.receiver_dropped {
+            return Err(value);
+        }
+        // the receiver is woken when `self` is dropped on return
+        state.value = Some(value);
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        (*self.state).borrow().receiver_dropped
+    }
+}
+
+impl <T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers: Vec<_> = {
+            let mut state = (*self.state).borrow_mut();
+            state.sender_dropped = true;
+            state.waiters.drain().collect()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+#[derive(Debug)]
+pub struct Receiver<T> {
+    state: Pointer<RefCell<OneshotState<T>>>,
+    waker_id: WakerId,
+}
+
+impl <T> Receiver<T> {
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut state = (*self.state).borrow_mut();
+        match state.value.take() {
+            Some(value) => Ok(value),
+            None if state.sender_dropped => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Stops the sender from sending; a value sent before the call can still
+    /// be received.
+    pub fn close(&mut self) {
+        (*self.state).borrow_mut().receiver_dropped = true;
+    }
+}
+
+impl <T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = (*self.state).borrow_mut();
+        match state.value.take() {
+            Some(value) => Poll::Ready(Ok(value)),
+            None if state.sender_dropped => Poll::Ready(Err(RecvError(()))),
+            None => {
+                state.waiters.register(self.waker_id, cx.waker(), ());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl <T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        (*self.state).borrow_mut().receiver_dropped = true;
+    }
+}
@@ -0,0 +1,69 @@
+// Contended waits and hold periods become `performance.measure` entries, so
+// they show up in the browser's Performance panel. Outside the browser there
+// is no timeline to put them on, and marks record nothing.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod web {
+    use core::cell::Cell;
+    use std::format;
+    use std::string::String;
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance)]
+        fn mark(name: &str);
+
+        #[wasm_bindgen(js_namespace = performance)]
+        fn measure(name: &str, start_mark: &str);
+
+        #[wasm_bindgen(js_namespace = performance, js_name = clearMarks)]
+        fn clear_marks(name: &str);
+    }
+
+    std::thread_local! {
+        static NEXT: Cell<u64> = const { Cell::new(0) };
+    }
+
+    /// A `performance.mark` that is measured up to when it is dropped.
+    #[derive(Debug)]
+    pub(crate) struct Mark {
+        measure: String,
+        mark: String,
+    }
+
+    impl Mark {
+        pub(crate) fn start(name: Option<&'static str>, mutex: usize, kind: &str) -> Self {
+            let measure = match name {
+                Some(name) => format!("{} {}", name, kind),
+                None => format!("Mutex@{:#x} {}", mutex, kind),
+            };
+            // overlapping waits on one mutex each need a mark of their own
+            let id = NEXT.get();
+            NEXT.set(id + 1);
+            let mark_name = format!("{} #{}", measure, id);
+            mark(&mark_name);
+            Mark { measure, mark: mark_name }
+        }
+    }
+
+    impl Drop for Mark {
+        fn drop(&mut self) {
+            measure(&self.measure, &self.mark);
+            clear_marks(&self.mark);
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) use web::Mark;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[derive(Debug)]
+pub(crate) struct Mark;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Mark {
+    pub(crate) fn start(_name: Option<&'static str>, _mutex: usize, _kind: &str) -> Self {
+        Mark
+    }
+}
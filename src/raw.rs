@@ -0,0 +1,85 @@
+use core::cell::Cell;
+
+/// A raw lock for `lock_api` and `embassy-sync`, for synchronous locking on
+/// the single wasm thread.
+///
+/// With `lock_api` it gives a mutex with `const` construction and mappable
+/// guards. Nothing else can run while a synchronous `lock` waits, so locking
+/// a held `RawLocalMutex` that way panics instead of deadlocking. Use
+/// `try_lock` where the lock may already be held, or `Mutex` when the holder
+/// can await.
+///
+/// With `embassy-sync` it is the raw mutex for embassy's blocking and async
+/// primitives. Those only need exclusion from other threads, which a value
+/// that is not `Sync` already has, so it just runs the closure.
+#[derive(Debug, Default)]
+pub struct RawLocalMutex {
+    // only read by the `lock_api` impl
+    #[cfg_attr(not(feature = "lock_api"), allow(dead_code))]
+    locked: Cell<bool>,
+}
+
+#[cfg(feature = "lock_api")]
+mod lock_api_impl {
+    use core::cell::Cell;
+    use lock_api::{GuardNoSend, RawMutex, RawMutexFair};
+    use super::RawLocalMutex;
+
+    // SAFETY: the lock is only ever handed to one guard at a time, and the
+    // `Cell` keeps it on the thread that created it.
+    unsafe impl RawMutex for RawLocalMutex {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const INIT: Self = RawLocalMutex { locked: Cell::new(false) };
+
+        type GuardMarker = GuardNoSend;
+
+        // with `panic_free` a relock aborts, since waiting would never end
+        // and `lock` has no way to fail
+        fn lock(&self) {
+            #[cfg(not(feature = "panic_free"))]
+            assert!(self.try_lock(), "RawLocalMutex::lock would deadlock: the lock is already held");
+            #[cfg(feature = "panic_free")]
+            if !self.try_lock() {
+                crate::abort();
+            }
+        }
+
+        fn try_lock(&self) -> bool {
+            !self.locked.replace(true)
+        }
+
+        unsafe fn unlock(&self) {
+            self.locked.set(false);
+        }
+
+        fn is_locked(&self) -> bool {
+            self.locked.get()
+        }
+    }
+
+    // SAFETY: with no waiters, a fair unlock is the same as a plain one.
+    unsafe impl RawMutexFair for RawLocalMutex {
+        unsafe fn unlock_fair(&self) {
+            self.locked.set(false);
+        }
+    }
+
+    pub type LocalMutex<T> = lock_api::Mutex<RawLocalMutex, T>;
+    pub type LocalMutexGuard<'a, T> = lock_api::MutexGuard<'a, RawLocalMutex, T>;
+    pub type MappedLocalMutexGuard<'a, T> = lock_api::MappedMutexGuard<'a, RawLocalMutex, T>;
+}
+
+#[cfg(feature = "lock_api")]
+pub use lock_api_impl::{LocalMutex, LocalMutexGuard, MappedLocalMutexGuard};
+
+// SAFETY: `RawLocalMutex` is not `Sync`, so no other thread can ever reach
+// it, let alone lock it concurrently.
+#[cfg(feature = "embassy")]
+unsafe impl embassy_sync::blocking_mutex::raw::RawMutex for RawLocalMutex {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = RawLocalMutex { locked: Cell::new(false) };
+
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+}
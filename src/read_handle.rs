@@ -0,0 +1,62 @@
+use core::task::{Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use core::ops::Deref;
+use crate::{Mutex, MutexRef, LockFuture, Subscription};
+
+impl <T: ?Sized> Mutex<T> {
+    /// Returns a handle to the same value that can only read it.
+    pub fn reader(&self) -> ReadHandle<T> {
+        ReadHandle { mutex: self.clone() }
+    }
+}
+
+/// A handle to a `Mutex` whose guards only give shared access. Readers still
+/// take the lock exclusively, in turn with writers.
+pub struct ReadHandle<T: ?Sized> {
+    mutex: Mutex<T>,
+}
+
+impl <T: ?Sized> Clone for ReadHandle<T> {
+    fn clone(&self) -> Self {
+        ReadHandle { mutex: self.mutex.clone() }
+    }
+}
+
+impl <T: ?Sized> ReadHandle<T> {
+    pub fn read(&self) -> ReadHandleFuture<'_, T> {
+        ReadHandleFuture { lock: self.mutex.lock() }
+    }
+
+    pub fn try_read(&self) -> Option<ReadHandleRef<'_, T>> {
+        self.mutex.try_lock().map(|guard| ReadHandleRef { guard })
+    }
+
+    pub fn subscribe(&self) -> Subscription<T> {
+        self.mutex.subscribe()
+    }
+}
+
+pub struct ReadHandleRef<'a, T: ?Sized> {
+    guard: MutexRef<'a, T>,
+}
+
+impl <'a, T: ?Sized> Deref for ReadHandleRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+pub struct ReadHandleFuture<'a, T: ?Sized> {
+    lock: LockFuture<'a, T>,
+}
+
+impl <'a, T: ?Sized> Future for ReadHandleFuture<'a, T> {
+    type Output = ReadHandleRef<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.lock).poll(cx).map(|guard| ReadHandleRef { guard })
+    }
+}
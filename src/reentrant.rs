@@ -0,0 +1,189 @@
+use core::cell::RefCell;
+use core::sync::atomic::Ordering;
+// embedded targets may lack 64-bit atomics
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64 as AtomicCounter;
+#[cfg(not(target_has_atomic = "64"))]
+use core::sync::atomic::AtomicUsize as AtomicCounter;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use core::ops::Deref;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+/// Identifies whoever holds a `ReentrantMutex`. Futures do not know which
+/// task they run on, so callers pass the same token down wherever they want
+/// to re-enter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LockOwner(u64);
+
+impl LockOwner {
+    /// Returns a token distinct from every other one created by this process.
+    pub fn new() -> Self {
+        static NEXT: AtomicCounter = AtomicCounter::new(0);
+        // the counter is only a usize where 64-bit atomics are missing
+        #[allow(clippy::unnecessary_cast)]
+        LockOwner(NEXT.fetch_add(1, Ordering::Relaxed) as u64)
+    }
+}
+
+impl Default for LockOwner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReentrantState {
+    owner: Option<LockOwner>,
+    depth: usize,
+    waiters: Waiters<LockOwner>,
+}
+
+impl ReentrantState {
+    fn try_enter(&mut self, owner: LockOwner) -> bool {
+        match self.owner {
+            Some(current) if current == owner => {
+                self.depth += 1;
+                true
+            }
+            None => {
+                self.owner = Some(owner);
+                self.depth = 1;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    // Hands the lock to the oldest waiter once the outermost guard is gone.
+    fn exit(&mut self) -> Option<Waker> {
+        self.depth -= 1;
+        if self.depth > 0 {
+            return None;
+        }
+        self.owner = self.waiters.front().copied();
+        if self.owner.is_some() {
+            self.depth = 1;
+        }
+        self.waiters.grant_front()
+    }
+}
+
+/// A mutex that its owner can lock again while already holding it. Guards
+/// only give shared access, since several of them may be alive at once.
+#[derive(Debug)]
+pub struct ReentrantMutex<T: ?Sized> {
+    value: Pointer<T>,
+    state: Pointer<RefCell<ReentrantState>>,
+}
+
+impl <T: ?Sized> Clone for ReentrantMutex<T> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), state: self.state.clone() }
+    }
+}
+
+impl <T: Default> Default for ReentrantMutex<T> {
+    fn default() -> Self {
+        Self { value: Default::default(), state: Default::default() }
+    }
+}
+
+impl <T> ReentrantMutex<T> {
+    pub fn new(value: T) -> Self {
+        ReentrantMutex {
+            value: Pointer::new(value),
+            state: Default::default(),
+        }
+    }
+}
+
+impl <T: ?Sized> ReentrantMutex<T> {
+    pub fn lock(&self, owner: LockOwner) -> ReentrantLockFuture<'_, T> {
+        ReentrantLockFuture {
+            waker_id: (*self.state).borrow_mut().waiters.next_id(),
+            owner,
+            mutex: self,
+        }
+    }
+
+    pub fn try_lock(&self, owner: LockOwner) -> Option<ReentrantMutexRef<'_, T>> {
+        if (*self.state).borrow_mut().try_enter(owner) {
+            Some(ReentrantMutexRef { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        (*self.state).borrow().owner.is_some()
+    }
+
+    /// Number of guards currently held by the owner.
+    pub fn depth(&self) -> usize {
+        (*self.state).borrow().depth
+    }
+
+    fn exit(&self) {
+        let waker = (*self.state).borrow_mut().exit();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+pub struct ReentrantMutexRef<'a, T: ?Sized> {
+    mutex: &'a ReentrantMutex<T>,
+}
+
+impl <'a, T: ?Sized> Deref for ReentrantMutexRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.mutex.value
+    }
+}
+
+impl <'a, T: ?Sized> Drop for ReentrantMutexRef<'a, T> {
+    fn drop(&mut self) {
+        self.mutex.exit();
+    }
+}
+
+pub struct ReentrantLockFuture<'a, T: ?Sized> {
+    waker_id: WakerId,
+    owner: LockOwner,
+    mutex: &'a ReentrantMutex<T>,
+}
+
+impl <'a, T: ?Sized> Future for ReentrantLockFuture<'a, T> {
+    type Output = ReentrantMutexRef<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mutex = self.mutex;
+        let mut state = (*mutex.state).borrow_mut();
+        if state.waiters.take_grant(self.waker_id) {
+            Poll::Ready(ReentrantMutexRef { mutex })
+        } else if state.try_enter(self.owner) {
+            // the owner may have taken the lock through another future while
+            // this one was queued
+            state.waiters.cancel(self.waker_id);
+            Poll::Ready(ReentrantMutexRef { mutex })
+        } else {
+            state.waiters.register(self.waker_id, cx.waker(), self.owner);
+            Poll::Pending
+        }
+    }
+}
+
+impl <'a, T: ?Sized> Drop for ReentrantLockFuture<'a, T> {
+    fn drop(&mut self) {
+        let granted = (*self.mutex.state).borrow_mut().waiters.cancel(self.waker_id);
+        // the lock was already handed to this future, so pass it on
+        if granted {
+            self.mutex.exit();
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+use std::panic::Location;
+use std::string::String;
+use std::vec::Vec;
+use crate::{console, WeakPointer};
+
+/// Implemented by the shared state of every lock that `dump_locks` lists.
+pub(crate) trait LockInfo {
+    /// Appends one line describing the lock, without a trailing newline.
+    fn describe(&self, out: &mut String);
+
+    /// Returns `None` if the lock is in the middle of an operation.
+    fn snapshot(&self) -> Option<LockSnapshot>;
+}
+
+/// The state of one live `Mutex`, as returned by `snapshot`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockSnapshot {
+    pub name: Option<&'static str>,
+    /// Identifies the mutex for as long as it lives; clones share it.
+    pub address: usize,
+    pub held: bool,
+    /// Where the guard holding the lock was asked for. `None` while the
+    /// lock is free or being handed to a waiter.
+    pub holder: Option<&'static Location<'static>>,
+    pub waiters: usize,
+}
+
+std::thread_local! {
+    // handles cannot leave the thread that created them, and neither can
+    // the registry of them
+    static LOCKS: RefCell<Vec<WeakPointer<dyn LockInfo>>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) fn register(lock: WeakPointer<dyn LockInfo>) {
+    LOCKS.with_borrow_mut(|locks| {
+        // drop dead entries before the list would have to grow
+        if locks.len() == locks.capacity() {
+            locks.retain(|lock| lock.strong_count() > 0);
+        }
+        locks.push(lock);
+    });
+}
+
+/// Describes every live `Mutex` created on this thread, one per line, in
+/// creation order: whether it is held, where the holder asked for it, and
+/// how many futures are waiting.
+pub fn lock_report() -> String {
+    let locks = live_locks();
+    let mut out = String::new();
+    for lock in locks {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        lock.describe(&mut out);
+    }
+    out
+}
+
+/// Describes every live `Mutex` created on this thread, in creation order,
+/// for tools that present the locks themselves. With the `serde` feature,
+/// each `LockSnapshot` serializes to a map.
+pub fn snapshot() -> Vec<LockSnapshot> {
+    live_locks().iter().filter_map(|lock| lock.snapshot()).collect()
+}
+
+fn live_locks() -> Vec<crate::Pointer<dyn LockInfo>> {
+    LOCKS.with_borrow(|locks| locks.iter().filter_map(WeakPointer::upgrade).collect())
+}
+
+/// Prints `lock_report` to the browser console, or to stderr outside the
+/// browser.
+pub fn dump_locks() {
+    console::log(&lock_report());
+}
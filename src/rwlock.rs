@@ -0,0 +1,293 @@
+use core::cell::{RefCell, UnsafeCell};
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use core::ops::{Deref, DerefMut};
+use core::fmt;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Default)]
+struct RwLockState {
+    readers: usize,
+    writer: bool,
+    waiters: Waiters<Access>,
+}
+
+impl RwLockState {
+    fn try_acquire(&mut self, access: Access) -> bool {
+        match access {
+            Access::Read if !self.writer => {
+                self.readers += 1;
+                true
+            }
+            Access::Write if !self.writer && self.readers == 0 => {
+                self.writer = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn release(&mut self, access: Access) {
+        match access {
+            Access::Read => self.readers -= 1,
+            Access::Write => self.writer = false,
+        }
+    }
+
+    // Grants queued waiters in order for as long as the front one is
+    // compatible with the current holders, so a run of readers is let in
+    // together while a queued writer holds back any readers behind it.
+    fn dispatch(&mut self) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        while let Some(access) = self.waiters.front().copied() {
+            if !self.try_acquire(access) {
+                break;
+            }
+            wakers.extend(self.waiters.grant_front());
+        }
+        wakers
+    }
+}
+
+pub struct RwLock<T: ?Sized> {
+    value: Pointer<UnsafeCell<T>>,
+    state: Pointer<RefCell<RwLockState>>,
+}
+
+impl <T: ?Sized> Clone for RwLock<T> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), state: self.state.clone() }
+    }
+}
+
+impl <T: fmt::Debug + ?Sized> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RwLock");
+        self.peek(|value| match value {
+            Some(value) => d.field("value", &value),
+            None => d.field("value", &format_args!("<locked, {} waiters>", (*self.state).borrow().waiters.len())),
+        });
+        d.finish()
+    }
+}
+
+impl <T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self { value: Default::default(), state: Default::default() }
+    }
+}
+
+impl <T> RwLock<T> {
+    pub fn new(value: T) -> Self {
+        RwLock {
+            value: Pointer::new(UnsafeCell::new(value)),
+            state: Default::default(),
+        }
+    }
+}
+
+impl <T: ?Sized> RwLock<T> {
+    /// Stores an unsized value such as `Box<dyn Trait>` without boxing it
+    /// twice.
+    pub fn from_box(value: Box<T>) -> Self {
+        let value: Pointer<T> = Pointer::from(value);
+        // SAFETY: `UnsafeCell<T>` has the same layout as `T`, so the pointer
+        // keeps its metadata and still addresses the same allocation.
+        let value = unsafe { Pointer::from_raw(Pointer::into_raw(value) as *const UnsafeCell<T>) };
+        RwLock { value, state: Default::default() }
+    }
+
+    pub fn read(&self) -> ReadFuture<'_, T> {
+        ReadFuture {
+            waker_id: self.next_waker_id(),
+            lock: self,
+        }
+    }
+
+    pub fn write(&self) -> WriteFuture<'_, T> {
+        WriteFuture {
+            waker_id: self.next_waker_id(),
+            lock: self,
+        }
+    }
+
+    pub fn try_read(&self) -> Option<RwLockReadRef<'_, T>> {
+        if self.acquire(Access::Read) {
+            Some(RwLockReadRef { lock: self })
+        } else {
+            None
+        }
+    }
+
+    pub fn try_write(&self) -> Option<RwLockWriteRef<'_, T>> {
+        if self.acquire(Access::Write) {
+            Some(RwLockWriteRef { lock: self })
+        } else {
+            None
+        }
+    }
+
+    // Newcomers only get in ahead of the queue when nobody is waiting,
+    // otherwise a steady stream of readers could starve a queued writer.
+    fn acquire(&self, access: Access) -> bool {
+        let mut state = (*self.state).borrow_mut();
+        state.waiters.is_empty() && state.try_acquire(access)
+    }
+
+    fn poll_acquire(&self, waker_id: WakerId, access: Access, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = (*self.state).borrow_mut();
+        let granted = state.waiters.take_grant(waker_id);
+        if granted || (state.waiters.is_empty() && state.try_acquire(access)) {
+            Poll::Ready(())
+        } else {
+            state.waiters.register(waker_id, cx.waker(), access);
+            Poll::Pending
+        }
+    }
+
+    // Reads the value in place unless a writer holds it. Unlike `try_read`,
+    // this never wakes waiters on the way out.
+    pub(crate) fn peek<R>(&self, f: impl FnOnce(Option<&T>) -> R) -> R {
+        // held so that the value cannot be locked for writing while `f` runs
+        let state = (*self.state).borrow();
+        if state.writer {
+            f(None)
+        } else {
+            // SAFETY: there is no writer, and none can get in while the
+            // state is borrowed.
+            f(Some(unsafe { &*self.value.get() }))
+        }
+    }
+
+    fn next_waker_id(&self) -> WakerId {
+        (*self.state).borrow_mut().waiters.next_id()
+    }
+
+    fn release(&self, access: Access) {
+        let wakers = {
+            let mut state = (*self.state).borrow_mut();
+            state.release(access);
+            state.dispatch()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    fn cancel(&self, waker_id: WakerId, access: Access) {
+        let wakers = {
+            let mut state = (*self.state).borrow_mut();
+            if state.waiters.cancel(waker_id) {
+                state.release(access);
+            }
+            // a cancelled writer may have been holding back readers
+            state.dispatch()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+pub struct RwLockReadRef<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+}
+
+impl <'a, T: ?Sized> Deref for RwLockReadRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: no writer can hold the lock while this guard is alive.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl <'a, T: fmt::Debug + ?Sized> fmt::Debug for RwLockReadRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl <'a, T: ?Sized> Drop for RwLockReadRef<'a, T> {
+    fn drop(&mut self) {
+        self.lock.release(Access::Read);
+    }
+}
+
+pub struct RwLockWriteRef<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+}
+
+impl <'a, T: ?Sized> Deref for RwLockWriteRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held exclusively for as long as this guard is alive.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl <'a, T: ?Sized> DerefMut for RwLockWriteRef<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the lock is held exclusively for as long as this guard is alive.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl <'a, T: fmt::Debug + ?Sized> fmt::Debug for RwLockWriteRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl <'a, T: ?Sized> Drop for RwLockWriteRef<'a, T> {
+    fn drop(&mut self) {
+        self.lock.release(Access::Write);
+    }
+}
+
+pub struct ReadFuture<'a, T: ?Sized> {
+    waker_id: WakerId,
+    lock: &'a RwLock<T>,
+}
+
+impl <'a, T: ?Sized> Future for ReadFuture<'a, T> {
+    type Output = RwLockReadRef<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = self.lock;
+        lock.poll_acquire(self.waker_id, Access::Read, cx).map(|()| RwLockReadRef { lock })
+    }
+}
+
+impl <'a, T: ?Sized> Drop for ReadFuture<'a, T> {
+    fn drop(&mut self) {
+        self.lock.cancel(self.waker_id, Access::Read);
+    }
+}
+
+pub struct WriteFuture<'a, T: ?Sized> {
+    waker_id: WakerId,
+    lock: &'a RwLock<T>,
+}
+
+impl <'a, T: ?Sized> Future for WriteFuture<'a, T> {
+    type Output = RwLockWriteRef<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = self.lock;
+        lock.poll_acquire(self.waker_id, Access::Write, cx).map(|()| RwLockWriteRef { lock })
+    }
+}
+
+impl <'a, T: ?Sized> Drop for WriteFuture<'a, T> {
+    fn drop(&mut self) {
+        self.lock.cancel(self.waker_id, Access::Write);
+    }
+}
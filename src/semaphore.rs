@@ -0,0 +1,135 @@
+use core::cell::RefCell;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug, Default)]
+struct SemaphoreState {
+    permits: usize,
+    waiters: Waiters,
+}
+
+impl SemaphoreState {
+    fn dispatch(&mut self) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        while self.permits > 0 {
+            match self.waiters.grant_front() {
+                Some(waker) => {
+                    self.permits -= 1;
+                    wakers.push(waker);
+                }
+                None => break,
+            }
+        }
+        wakers
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Semaphore {
+    state: Pointer<RefCell<SemaphoreState>>,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Semaphore {
+            state: Pointer::new(RefCell::new(SemaphoreState {
+                permits,
+                waiters: Default::default(),
+            })),
+        }
+    }
+
+    pub fn acquire(&self) -> AcquireFuture<'_> {
+        AcquireFuture {
+            waker_id: (*self.state).borrow_mut().waiters.next_id(),
+            semaphore: self,
+        }
+    }
+
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        let mut state = (*self.state).borrow_mut();
+        if state.permits > 0 && state.waiters.is_empty() {
+            state.permits -= 1;
+            Some(Permit { semaphore: self })
+        } else {
+            None
+        }
+    }
+
+    pub fn available_permits(&self) -> usize {
+        (*self.state).borrow().permits
+    }
+
+    pub fn add_permits(&self, permits: usize) {
+        let wakers = {
+            let mut state = (*self.state).borrow_mut();
+            state.permits += permits;
+            state.dispatch()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    fn poll_acquire(&self, waker_id: WakerId, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = (*self.state).borrow_mut();
+        if state.waiters.take_grant(waker_id) {
+            Poll::Ready(())
+        } else if state.permits > 0 && state.waiters.is_empty() {
+            state.permits -= 1;
+            Poll::Ready(())
+        } else {
+            state.waiters.register(waker_id, cx.waker(), ());
+            Poll::Pending
+        }
+    }
+
+    fn cancel(&self, waker_id: WakerId) {
+        let wakers = {
+            let mut state = (*self.state).borrow_mut();
+            if state.waiters.cancel(waker_id) {
+                state.permits += 1;
+            }
+            state.dispatch()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+pub struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl <'a> Permit<'a> {
+    pub fn forget(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl <'a> Drop for Permit<'a> {
+    fn drop(&mut self) {
+        self.semaphore.add_permits(1);
+    }
+}
+
+pub struct AcquireFuture<'a> {
+    waker_id: WakerId,
+    semaphore: &'a Semaphore,
+}
+
+impl <'a> Future for AcquireFuture<'a> {
+    type Output = Permit<'a>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore;
+        semaphore.poll_acquire(self.waker_id, cx).map(|()| Permit { semaphore })
+    }
+}
+
+impl <'a> Drop for AcquireFuture<'a> {
+    fn drop(&mut self) {
+        self.semaphore.cancel(self.waker_id);
+    }
+}
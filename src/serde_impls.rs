@@ -0,0 +1,64 @@
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::Error;
+use crate::{Mutex, RwLock};
+#[cfg(feature = "registry")]
+use crate::LockSnapshot;
+
+impl <T: Serialize + ?Sized> Serialize for Mutex<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        self.peek(|value| match value {
+            Some(value) => serializer.serialize_newtype_struct("Mutex", value),
+            None => Err(S::Error::custom("already mutably borrowed")),
+        })
+    }
+}
+
+impl <'de, T: Deserialize<'de>> Deserialize<'de> for Mutex<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        Ok(Mutex::new(T::deserialize(deserializer)?))
+    }
+}
+
+impl <T: Serialize + ?Sized> Serialize for RwLock<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        self.peek(|value| match value {
+            Some(value) => serializer.serialize_newtype_struct("RwLock", value),
+            None => Err(S::Error::custom("already mutably borrowed")),
+        })
+    }
+}
+
+impl <'de, T: Deserialize<'de>> Deserialize<'de> for RwLock<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        Ok(RwLock::new(T::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(feature = "registry")]
+impl Serialize for LockSnapshot {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        use serde::ser::SerializeStruct;
+
+        struct Site(&'static std::panic::Location<'static>);
+
+        impl Serialize for Site {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where S: Serializer {
+                serializer.collect_str(self.0)
+            }
+        }
+
+        let mut snapshot = serializer.serialize_struct("LockSnapshot", 5)?;
+        snapshot.serialize_field("name", &self.name)?;
+        snapshot.serialize_field("address", &self.address)?;
+        snapshot.serialize_field("held", &self.held)?;
+        snapshot.serialize_field("holder", &self.holder.map(Site))?;
+        snapshot.serialize_field("waiters", &self.waiters)?;
+        snapshot.end()
+    }
+}
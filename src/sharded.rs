@@ -0,0 +1,61 @@
+use std::hash::{BuildHasher, Hash, RandomState};
+use crate::{Mutex, MutexRef, LockFuture, Pointer};
+
+/// Splits state across a fixed number of mutexes, picking one by the hash of
+/// a key, so tasks working on different keys rarely wait on each other.
+pub struct ShardedMutex<T> {
+    shards: Pointer<[Mutex<T>]>,
+    hasher: RandomState,
+}
+
+/// A `ShardedMutex` that only serializes access, holding no state itself.
+pub type StripedLock = ShardedMutex<()>;
+
+impl <T> Clone for ShardedMutex<T> {
+    fn clone(&self) -> Self {
+        ShardedMutex { shards: self.shards.clone(), hasher: self.hasher.clone() }
+    }
+}
+
+impl <T: Default> ShardedMutex<T> {
+    pub fn new(shards: usize) -> Self {
+        Self::from_fn(shards, |_| T::default())
+    }
+}
+
+impl <T> ShardedMutex<T> {
+    /// Builds each shard's value with `f`, given the shard's index. Zero
+    /// shards panics, or is taken as one with `panic_free`.
+    pub fn from_fn(shards: usize, mut f: impl FnMut(usize) -> T) -> Self {
+        #[cfg(not(feature = "panic_free"))]
+        assert!(shards > 0, "ShardedMutex requires at least one shard");
+        #[cfg(feature = "panic_free")]
+        let shards = shards.max(1);
+        ShardedMutex {
+            shards: (0..shards).map(|index| Mutex::new(f(index))).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub fn shard_index<K: Hash + ?Sized>(&self, key: &K) -> usize {
+        (self.hasher.hash_one(key) % self.shards.len() as u64) as usize
+    }
+
+    // `shard_index` is always below the number of shards
+    #[allow(clippy::indexing_slicing)]
+    pub fn shard<K: Hash + ?Sized>(&self, key: &K) -> &Mutex<T> {
+        &self.shards[self.shard_index(key)]
+    }
+
+    pub fn shards(&self) -> &[Mutex<T>] {
+        &self.shards
+    }
+
+    pub fn lock<K: Hash + ?Sized>(&self, key: &K) -> LockFuture<'_, T> {
+        self.shard(key).lock()
+    }
+
+    pub fn try_lock<K: Hash + ?Sized>(&self, key: &K) -> Option<MutexRef<'_, T>> {
+        self.shard(key).try_lock()
+    }
+}
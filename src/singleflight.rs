@@ -0,0 +1,77 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use crate::{OnceCell, Pointer};
+
+/// Coalesces concurrent calls for the same key into a single execution.
+pub struct Singleflight<K, V> {
+    flights: Pointer<RefCell<HashMap<K, Flight<V>>>>,
+}
+
+struct Flight<V> {
+    result: OnceCell<V>,
+    // calls waiting on `result`, so the last one to be cancelled can clear
+    // the flight
+    callers: usize,
+}
+
+impl <K, V> Clone for Singleflight<K, V> {
+    fn clone(&self) -> Self {
+        Singleflight { flights: self.flights.clone() }
+    }
+}
+
+impl <K, V> Default for Singleflight<K, V> {
+    fn default() -> Self {
+        Singleflight { flights: Default::default() }
+    }
+}
+
+impl <K: Eq + Hash + Clone, V: Clone> Singleflight<K, V> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Runs `f` unless a call for `key` is already in flight, in which case
+    /// this waits for that call and returns a copy of its result. Once a
+    /// result is out, the next call for `key` runs `f` again. If the running
+    /// call is cancelled, one of the waiting calls takes over with its own `f`.
+    pub async fn run<F, Fut>(&self, key: K, f: F) -> V
+    where F: FnOnce() -> Fut, Fut: Future<Output = V> {
+        let result = {
+            let mut flights = (*self.flights).borrow_mut();
+            let flight = flights.entry(key.clone()).or_insert_with(|| Flight { result: OnceCell::new(), callers: 0 });
+            flight.callers += 1;
+            flight.result.clone()
+        };
+        let caller = Caller { flights: self, key, result };
+        caller.result.get_or_init(f).await.clone()
+    }
+
+    /// Reports whether a call for `key` is in flight.
+    pub fn is_running(&self, key: &K) -> bool {
+        (*self.flights).borrow().contains_key(key)
+    }
+}
+
+// Leaves the flight on drop, whether the call finished or was cancelled.
+struct Caller<'a, K: Eq + Hash, V> {
+    flights: &'a Singleflight<K, V>,
+    key: K,
+    result: OnceCell<V>,
+}
+
+impl <'a, K: Eq + Hash, V> Drop for Caller<'a, K, V> {
+    fn drop(&mut self) {
+        let mut flights = (*self.flights.flights).borrow_mut();
+        // a later flight for the same key may have replaced this one
+        let Some(flight) = flights.get_mut(&self.key).filter(|flight| flight.result.ptr_eq(&self.result)) else {
+            return;
+        };
+        flight.callers -= 1;
+        if flight.callers == 0 || flight.result.get().is_some() {
+            flights.remove(&self.key);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use core::pin::Pin;
+use core::future::Future;
+use core::task::{ready, Context, Poll};
+use futures_sink::Sink;
+use crate::{Mutex, OwnedLockFuture, OwnedMutexRef};
+
+/// Shares one sink between producers. The lock is taken in `poll_ready` and
+/// held until the matching `start_send`, so no other producer can use up the
+/// capacity in between. Flushing and closing hold it until they complete, so
+/// only the producer holding the lock is ever parked on the inner sink.
+pub struct GuardedSink<S: ?Sized> {
+    mutex: Mutex<S>,
+    lock: Option<OwnedLockFuture<S>>,
+    guard: Option<OwnedMutexRef<S>>,
+}
+
+impl <S: ?Sized> GuardedSink<S> {
+    pub fn new(mutex: Mutex<S>) -> Self {
+        GuardedSink { mutex, lock: None, guard: None }
+    }
+
+    pub fn get_ref(&self) -> &Mutex<S> {
+        &self.mutex
+    }
+
+    pub fn into_inner(self) -> Mutex<S> {
+        self.mutex
+    }
+
+    fn poll_guard(&mut self, cx: &mut Context<'_>) -> Poll<&mut OwnedMutexRef<S>> {
+        match self.guard {
+            Some(ref mut guard) => Poll::Ready(guard),
+            None => {
+                let lock = self.lock.get_or_insert_with(|| self.mutex.lock_owned());
+                let guard = ready!(Pin::new(lock).poll(cx));
+                self.lock = None;
+                Poll::Ready(self.guard.insert(guard))
+            }
+        }
+    }
+}
+
+impl <S: ?Sized> Unpin for GuardedSink<S> {}
+
+impl <S: Sink<Item> + Unpin + ?Sized, Item> Sink<Item> for GuardedSink<S> {
+    type Error = S::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        let guard = ready!(this.poll_guard(cx));
+        let result = ready!(Pin::new(&mut **guard).poll_ready(cx));
+        if result.is_err() {
+            this.guard = None;
+        }
+        Poll::Ready(result)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        #[cfg(not(feature = "panic_free"))]
+        let mut guard = this.guard.take().expect("GuardedSink::start_send called without a successful poll_ready");
+        // with `panic_free`, a send without `poll_ready` still goes through
+        // if the lock is free, and the item is dropped if it is not
+        #[cfg(feature = "panic_free")]
+        let Some(mut guard) = this.guard.take().or_else(|| this.mutex.try_lock_owned()) else { return Ok(()) };
+        Pin::new(&mut *guard).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        let guard = ready!(this.poll_guard(cx));
+        let result = ready!(Pin::new(&mut **guard).poll_flush(cx));
+        this.guard = None;
+        Poll::Ready(result)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        let guard = ready!(this.poll_guard(cx));
+        let result = ready!(Pin::new(&mut **guard).poll_close(cx));
+        this.guard = None;
+        Poll::Ready(result)
+    }
+}
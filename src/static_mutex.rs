@@ -0,0 +1,48 @@
+use std::thread::LocalKey;
+use crate::{Mutex, OwnedMutexRef, OwnedLockFuture};
+
+/// Declares a global, lazily-initialized `Mutex` backed by `thread_local!`.
+///
+/// ```
+/// wasm_mutex::static_mutex!(COUNTER: u32 = 0);
+///
+/// *COUNTER.try_lock().unwrap() += 1;
+/// assert_eq!(*COUNTER.handle().try_lock().unwrap(), 1);
+/// ```
+#[macro_export]
+macro_rules! static_mutex {
+    ($(#[$attr:meta])* $vis:vis $name:ident: $ty:ty = $init:expr $(;)?) => {
+        $(#[$attr])*
+        $vis static $name: $crate::StaticMutex<$ty> = {
+            ::std::thread_local! {
+                static INNER: $crate::Mutex<$ty> = $crate::Mutex::new($init);
+            }
+            $crate::StaticMutex::new(&INNER)
+        };
+    };
+}
+
+/// A global mutex declared with `static_mutex!`. The value is created on
+/// first access and every access from the same thread shares it.
+pub struct StaticMutex<T: 'static> {
+    key: &'static LocalKey<Mutex<T>>,
+}
+
+impl <T: 'static> StaticMutex<T> {
+    #[doc(hidden)]
+    pub const fn new(key: &'static LocalKey<Mutex<T>>) -> Self {
+        StaticMutex { key }
+    }
+
+    pub fn handle(&self) -> Mutex<T> {
+        self.key.with(Mutex::clone)
+    }
+
+    pub fn lock(&self) -> OwnedLockFuture<T> {
+        self.key.with(Mutex::lock_owned)
+    }
+
+    pub fn try_lock(&self) -> Option<OwnedMutexRef<T>> {
+        self.key.with(Mutex::try_lock_owned)
+    }
+}
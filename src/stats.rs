@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+/// Counters returned by `Mutex::stats`. They are shared by every handle to
+/// the mutex and count from its creation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MutexStats {
+    /// The name given to `MutexBuilder::name`, if any.
+    pub name: Option<&'static str>,
+    /// Guards handed out, by `try_lock` as well as by lock futures.
+    pub acquisitions: u64,
+    /// Acquisitions by futures that had to queue first.
+    pub contended: u64,
+    /// Futures queued right now.
+    pub waiters: usize,
+    /// The longest the queue has been.
+    pub max_waiters: usize,
+    /// Time spent queued, summed over every contended acquisition.
+    pub wait_time: Duration,
+}
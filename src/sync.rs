@@ -0,0 +1,538 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use alloc::boxed::Box;
+#[cfg(not(loom))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "std", not(loom)))]
+use std::sync::{Mutex as OsMutex, MutexGuard as OsMutexGuard};
+// loom checks every interleaving of the state lock and the handle counts
+#[cfg(loom)]
+use loom::sync::{Arc, Mutex as OsMutex, MutexGuard as OsMutexGuard};
+use core::task::{Waker, Context, Poll};
+#[cfg(not(loom))]
+use core::sync::atomic::{AtomicU8, Ordering};
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU8, Ordering};
+use futures_core::FusedFuture;
+use crate::waiters::{WakerId, Waiters};
+
+// Bits of `Inner::flags`. The lock is taken and given back with a single
+// atomic operation, and the state is only locked once `QUEUED` says that
+// somebody is waiting. `QUEUED` is only ever set while `LOCKED` is.
+const LOCKED: u8 = 1;
+const QUEUED: u8 = 2;
+
+#[derive(Debug, Default)]
+struct MutexState {
+    waiters: Waiters,
+}
+
+/// How long `lock` keeps retrying a held lock before queueing for it. For
+/// critical sections of a few instructions, another thread is likely to
+/// release the lock sooner than a waker round trip would take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Spin {
+    /// Retries before queueing. The default of zero queues straight away.
+    pub attempts: u32,
+    /// Spin loop hints before the first retry, doubled after every retry
+    /// up to `max_backoff`.
+    pub initial_backoff: u32,
+    pub max_backoff: u32,
+}
+
+struct Inner<T: ?Sized> {
+    flags: AtomicU8,
+    state: StateLock<MutexState>,
+    spin: Spin,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value is only reached through a guard, and the state mutex
+// makes sure at most one guard exists at a time.
+unsafe impl <T: ?Sized + Send> Send for Inner<T> {}
+unsafe impl <T: ?Sized + Send> Sync for Inner<T> {}
+
+/// A `Send + Sync` counterpart of the crate's `Mutex`, for values shared with
+/// web workers or native threads. Cloning it clones the handle. Waiters are
+/// served in arrival order, and a released lock is handed straight to the
+/// next one.
+pub struct Mutex<T: ?Sized> {
+    inner: Arc<Inner<T>>,
+}
+
+impl <T: ?Sized> Clone for Mutex<T> {
+    fn clone(&self) -> Self {
+        Mutex { inner: self.inner.clone() }
+    }
+}
+
+impl <T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Mutex::new(T::default())
+    }
+}
+
+impl <T: fmt::Debug + ?Sized> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.try_lock() {
+            Some(guard) => f.debug_tuple("Mutex").field(&&*guard).finish(),
+            None => write!(f, "Mutex(<locked>)"),
+        }
+    }
+}
+
+impl <T> Mutex<T> {
+    pub fn new(value: T) -> Self {
+        Mutex::with_spin(value, Spin::default())
+    }
+
+    /// Like `new`, but `lock` spins as `spin` says before it queues.
+    pub fn with_spin(value: T, spin: Spin) -> Self {
+        Mutex {
+            inner: Arc::new(Inner {
+                flags: AtomicU8::new(0),
+                state: Default::default(),
+                spin,
+                value: UnsafeCell::new(value),
+            }),
+        }
+    }
+
+    /// Recovers the value when this is the last handle to it, otherwise
+    /// hands the handle back.
+    pub fn into_inner(self) -> Result<T, Self> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => Ok(inner.value.into_inner()),
+            Err(inner) => Err(Mutex { inner }),
+        }
+    }
+}
+
+impl <T: ?Sized> Mutex<T> {
+    /// Borrows the value without locking when this is the only handle to it.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        Arc::get_mut(&mut self.inner).map(|inner| inner.value.get_mut())
+    }
+
+    pub fn lock(&self) -> LockFuture<'_, T> {
+        LockFuture { waker_id: None, done: false, mutex: self }
+    }
+
+    pub fn lock_owned(&self) -> OwnedLockFuture<T> {
+        OwnedLockFuture { waker_id: None, done: false, mutex: self.clone() }
+    }
+
+    pub fn try_lock(&self) -> Option<MutexRef<'_, T>> {
+        self.try_acquire().then(|| MutexRef::new(self))
+    }
+
+    pub fn try_lock_owned(&self) -> Option<OwnedMutexRef<T>> {
+        self.try_acquire().then(|| OwnedMutexRef::new(self.clone()))
+    }
+
+    /// Reports whether the lock is held, including while it is being handed
+    /// to a queued waiter.
+    pub fn is_locked(&self) -> bool {
+        self.inner.flags.load(Ordering::Acquire) & LOCKED != 0
+    }
+
+    pub fn waiter_count(&self) -> usize {
+        self.state().waiters.len()
+    }
+
+    /// Reports whether both handles point at the same mutex.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    fn state(&self) -> impl DerefMut<Target = MutexState> + '_ {
+        self.inner.state.lock()
+    }
+
+    // Fails while anybody is queued, since the lock is then handed from
+    // one waiter to the next without being unlocked in between.
+    fn try_acquire(&self) -> bool {
+        self.inner.flags.compare_exchange(0, LOCKED, Ordering::Acquire, Ordering::Relaxed).is_ok()
+    }
+
+    // Only a future that has not queued yet spins: one that has is handed
+    // the lock on release, so retrying could not get it any sooner.
+    fn spin(&self) -> bool {
+        let Spin { attempts, initial_backoff, max_backoff } = self.inner.spin;
+        let mut backoff = initial_backoff;
+        for _ in 0..attempts {
+            for _ in 0..backoff {
+                core::hint::spin_loop();
+            }
+            if self.try_acquire() {
+                return true;
+            }
+            backoff = backoff.saturating_mul(2).min(max_backoff);
+        }
+        false
+    }
+
+    fn poll_acquire(&self, waker_id: &mut Option<WakerId>, waker: &Waker) -> bool {
+        if waker_id.is_none() && (self.try_acquire() || self.spin()) {
+            return true;
+        }
+        let mut state = self.state();
+        if waker_id.is_some_and(|id| state.waiters.take_grant(id)) {
+            return true;
+        }
+        if self.lock_or_flag_queued() {
+            return true;
+        }
+        let waker_id = *waker_id.get_or_insert_with(|| state.waiters.next_id());
+        state.waiters.register(waker_id, waker, ());
+        false
+    }
+
+    // Run with the state locked. Once `QUEUED` is set, the holder's release
+    // has to lock the state too, so it cannot slip in before the waiter is
+    // registered and leave it unwoken.
+    fn lock_or_flag_queued(&self) -> bool {
+        let mut flags = self.inner.flags.load(Ordering::Relaxed);
+        loop {
+            let (next, acquired) = if flags & LOCKED == 0 {
+                (flags | LOCKED, true)
+            } else {
+                (flags | QUEUED, false)
+            };
+            match self.inner.flags.compare_exchange_weak(flags, next, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => return acquired,
+                Err(actual) => flags = actual,
+            }
+        }
+    }
+
+    fn release(&self) {
+        if self.inner.flags.compare_exchange(LOCKED, 0, Ordering::Release, Ordering::Relaxed).is_ok() {
+            return;
+        }
+        let waker = {
+            let mut state = self.state();
+            let waker = state.waiters.grant_front();
+            // Nothing else changes the flags while they say `QUEUED` and the
+            // state is locked. A granted waiter keeps the lock, and `QUEUED`
+            // stays up while there are others behind it.
+            let flags = match (&waker, state.waiters.is_empty()) {
+                (None, _) => 0,
+                (Some(_), true) => LOCKED,
+                (Some(_), false) => LOCKED | QUEUED,
+            };
+            self.inner.flags.store(flags, Ordering::Release);
+            waker
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn cancel(&self, waker_id: WakerId) {
+        // a future dropped after being handed the lock passes it on
+        if self.state().waiters.cancel(waker_id) {
+            self.release();
+        }
+    }
+}
+
+// Blocking needs threads to park, which neither loom's model nor the
+// browser's main thread provide, and `std::time::Instant` panics in the
+// browser.
+#[cfg(all(feature = "std", not(loom), not(all(target_arch = "wasm32", target_os = "unknown"))))]
+impl <T: ?Sized> Mutex<T> {
+    /// Takes the lock from synchronous code, parking the current thread
+    /// until it is free. Waits in the same queue as `lock`. Like
+    /// `std::sync::Mutex::lock`, this never returns if the current thread is
+    /// the one that has to release the lock, so it must not be called from
+    /// an async task.
+    pub fn blocking_lock(&self) -> MutexRef<'_, T> {
+        // without a deadline the wait only ends with the lock
+        loop {
+            if let Some(guard) = self.block_on(self.lock(), None) {
+                return guard;
+            }
+        }
+    }
+
+    /// Like `blocking_lock`, but gives up the place in the queue once
+    /// `timeout` has passed.
+    pub fn blocking_try_lock_for(&self, timeout: std::time::Duration) -> Option<MutexRef<'_, T>> {
+        self.block_on(self.lock(), Some(std::time::Instant::now() + timeout))
+    }
+
+    fn block_on<'a>(&self, mut lock: LockFuture<'a, T>, deadline: Option<std::time::Instant>) -> Option<MutexRef<'a, T>> {
+        let waker = Waker::from(std::sync::Arc::new(Unparker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(guard) = Pin::new(&mut lock).poll(&mut cx) {
+                return Some(guard);
+            }
+            // unparks that arrive early are kept, so a wake-up between the
+            // poll and the park is not lost, and spurious ones only cost a poll
+            match deadline {
+                None => std::thread::park(),
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                    if remaining.is_zero() {
+                        // dropping `lock` passes on a grant that raced the deadline
+                        return None;
+                    }
+                    std::thread::park_timeout(remaining);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "std", not(loom), not(all(target_arch = "wasm32", target_os = "unknown"))))]
+struct Unparker(std::thread::Thread);
+
+#[cfg(all(feature = "std", not(loom), not(all(target_arch = "wasm32", target_os = "unknown"))))]
+impl std::task::Wake for Unparker {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &std::sync::Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(any(feature = "std", loom))]
+#[derive(Default)]
+struct StateLock<S>(OsMutex<S>);
+
+#[cfg(any(feature = "std", loom))]
+impl <S> StateLock<S> {
+    // Nothing panics while the state is locked, so a poisoned state mutex
+    // can only come from a panic elsewhere on that thread and is still valid.
+    fn lock(&self) -> OsMutexGuard<'_, S> {
+        self.0.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+// Without std there is no OS mutex to park on. The state is only held for a
+// few instructions at a time, so spinning is cheap.
+#[cfg(not(any(feature = "std", loom)))]
+#[derive(Default)]
+struct StateLock<S> {
+    locked: core::sync::atomic::AtomicBool,
+    state: UnsafeCell<S>,
+}
+
+// SAFETY: the flag hands the state to one thread at a time.
+#[cfg(not(any(feature = "std", loom)))]
+unsafe impl <S: Send> Sync for StateLock<S> {}
+
+#[cfg(not(any(feature = "std", loom)))]
+impl <S> StateLock<S> {
+    fn lock(&self) -> SpinGuard<'_, S> {
+        use core::sync::atomic::Ordering;
+        while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            core::hint::spin_loop();
+        }
+        SpinGuard { lock: self }
+    }
+}
+
+#[cfg(not(any(feature = "std", loom)))]
+struct SpinGuard<'a, S> {
+    lock: &'a StateLock<S>,
+}
+
+#[cfg(not(any(feature = "std", loom)))]
+impl <'a, S> Deref for SpinGuard<'a, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        // SAFETY: the flag is held for as long as this guard is alive.
+        unsafe { &*self.lock.state.get() }
+    }
+}
+
+#[cfg(not(any(feature = "std", loom)))]
+impl <'a, S> DerefMut for SpinGuard<'a, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        // SAFETY: the flag is held for as long as this guard is alive.
+        unsafe { &mut *self.lock.state.get() }
+    }
+}
+
+#[cfg(not(any(feature = "std", loom)))]
+impl <'a, S> Drop for SpinGuard<'a, S> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, core::sync::atomic::Ordering::Release);
+    }
+}
+
+pub struct MutexRef<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    // the guard hands out `&T` and `&mut T`, so it is only `Sync` for `T: Sync`
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl <'a, T: ?Sized> MutexRef<'a, T> {
+    fn new(mutex: &'a Mutex<T>) -> Self {
+        MutexRef { mutex, _marker: PhantomData }
+    }
+}
+
+impl <'a, T: ?Sized> Deref for MutexRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &*self.mutex.inner.value.get() }
+    }
+}
+
+impl <'a, T: ?Sized> DerefMut for MutexRef<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &mut *self.mutex.inner.value.get() }
+    }
+}
+
+impl <'a, T: fmt::Debug + ?Sized> fmt::Debug for MutexRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl <'a, T: ?Sized> Drop for MutexRef<'a, T> {
+    fn drop(&mut self) {
+        self.mutex.release();
+    }
+}
+
+pub struct OwnedMutexRef<T: ?Sized> {
+    mutex: Mutex<T>,
+    _marker: PhantomData<Box<T>>,
+}
+
+impl <T: ?Sized> OwnedMutexRef<T> {
+    fn new(mutex: Mutex<T>) -> Self {
+        OwnedMutexRef { mutex, _marker: PhantomData }
+    }
+}
+
+impl <T: ?Sized> Deref for OwnedMutexRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &*self.mutex.inner.value.get() }
+    }
+}
+
+impl <T: ?Sized> DerefMut for OwnedMutexRef<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the lock is held for as long as this guard is alive.
+        unsafe { &mut *self.mutex.inner.value.get() }
+    }
+}
+
+impl <T: fmt::Debug + ?Sized> fmt::Debug for OwnedMutexRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl <T: ?Sized> Drop for OwnedMutexRef<T> {
+    fn drop(&mut self) {
+        self.mutex.release();
+    }
+}
+
+/// Resolves to a guard once the lock is taken. Polling it again after that
+/// panics, or stays pending with `panic_free`.
+pub struct LockFuture<'a, T: ?Sized> {
+    waker_id: Option<WakerId>,
+    done: bool,
+    mutex: &'a Mutex<T>,
+}
+
+impl <'a, T: ?Sized> Unpin for LockFuture<'a, T> {}
+
+impl <'a, T: ?Sized> Future for LockFuture<'a, T> {
+    type Output = MutexRef<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        #[cfg(not(feature = "panic_free"))]
+        assert!(!this.done, "LockFuture polled after completion");
+        #[cfg(feature = "panic_free")]
+        if this.done {
+            return Poll::Pending;
+        }
+        if this.mutex.poll_acquire(&mut this.waker_id, cx.waker()) {
+            this.done = true;
+            Poll::Ready(MutexRef::new(this.mutex))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl <'a, T: ?Sized> FusedFuture for LockFuture<'a, T> {
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
+
+impl <'a, T: ?Sized> Drop for LockFuture<'a, T> {
+    fn drop(&mut self) {
+        if let Some(waker_id) = self.waker_id {
+            self.mutex.cancel(waker_id);
+        }
+    }
+}
+
+/// Like `LockFuture`, but resolves to an owned guard.
+pub struct OwnedLockFuture<T: ?Sized> {
+    waker_id: Option<WakerId>,
+    done: bool,
+    mutex: Mutex<T>,
+}
+
+impl <T: ?Sized> Unpin for OwnedLockFuture<T> {}
+
+impl <T: ?Sized> Future for OwnedLockFuture<T> {
+    type Output = OwnedMutexRef<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        #[cfg(not(feature = "panic_free"))]
+        assert!(!this.done, "OwnedLockFuture polled after completion");
+        #[cfg(feature = "panic_free")]
+        if this.done {
+            return Poll::Pending;
+        }
+        if this.mutex.poll_acquire(&mut this.waker_id, cx.waker()) {
+            this.done = true;
+            Poll::Ready(OwnedMutexRef::new(this.mutex.clone()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl <T: ?Sized> FusedFuture for OwnedLockFuture<T> {
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
+
+impl <T: ?Sized> Drop for OwnedLockFuture<T> {
+    fn drop(&mut self) {
+        if let Some(waker_id) = self.waker_id {
+            self.mutex.cancel(waker_id);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::cell::Cell;
+#[cfg(debug_assertions)]
+use std::cell::RefCell;
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Identifies a future wrapped by `task_scope`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(pub(crate) u64);
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task #{}", self.0)
+    }
+}
+
+std::thread_local! {
+    static CURRENT: Cell<Option<TaskId>> = const { Cell::new(None) };
+    static NEXT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// The innermost `task_scope` being polled on this thread, if any.
+pub fn current_task() -> Option<TaskId> {
+    CURRENT.get()
+}
+
+/// Gives `future` a `TaskId` of its own, which `current_task` returns while
+/// it is being polled. In debug builds, a lock future queueing on a mutex
+/// held by its own task panics instead of waiting forever; with `panic_free`
+/// it logs the mistake to the console and waits. Futures outside any scope
+/// are not checked.
+pub fn task_scope<F: Future>(future: F) -> TaskScope<F> {
+    let id = NEXT.get();
+    NEXT.set(id + 1);
+    TaskScope { id: TaskId(id), future }
+}
+
+pub struct TaskScope<F> {
+    id: TaskId,
+    future: F,
+}
+
+impl <F> TaskScope<F> {
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+}
+
+impl <F: Future> Future for TaskScope<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // puts the outer scope back even if the future panics
+        struct Restore(Option<TaskId>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.set(self.0);
+            }
+        }
+
+        let _restore = Restore(CURRENT.replace(Some(self.id)));
+        // SAFETY: `future` is structurally pinned: it is never moved out of
+        // the scope, which is only `Unpin` when the future is.
+        unsafe { self.map_unchecked_mut(|scope| &mut scope.future) }.poll(cx)
+    }
+}
+
+#[cfg(debug_assertions)]
+struct HeldLock {
+    mutex: usize,
+    level: u32,
+    name: Option<&'static str>,
+}
+
+#[cfg(debug_assertions)]
+std::thread_local! {
+    // the leveled locks each scoped task holds
+    static HELD: RefCell<HashMap<TaskId, Vec<HeldLock>>> = RefCell::new(HashMap::new());
+}
+
+// Taking locks in increasing level order only is what rules out cycles, so
+// the current task must not hold a lock of this level or above.
+#[cfg(debug_assertions)]
+pub(crate) fn check_level(level: u32, name: Option<&'static str>) {
+    let Some(task) = current_task() else { return };
+    let highest = HELD.with_borrow(|held| {
+        let locks = held.get(&task)?;
+        locks.iter().max_by_key(|lock| lock.level).map(|lock| (lock.level, lock.name))
+    });
+    if let Some((held_level, held_name)) = highest.filter(|&(held_level, _)| held_level >= level) {
+        report(&format!(
+            "{} is locking {} at level {} while holding {} at level {}; leveled locks must be taken in increasing level order",
+            task, name.unwrap_or("a mutex"), level, held_name.unwrap_or("a mutex"), held_level,
+        ));
+    }
+}
+
+#[cfg(debug_assertions)]
+pub(crate) fn hold_level(task: TaskId, mutex: usize, level: u32, name: Option<&'static str>) {
+    HELD.with_borrow_mut(|held| held.entry(task).or_default().push(HeldLock { mutex, level, name }));
+}
+
+#[cfg(debug_assertions)]
+pub(crate) fn release_level(task: TaskId, mutex: usize) {
+    HELD.with_borrow_mut(|held| {
+        let Some(locks) = held.get_mut(&task) else { return };
+        locks.retain(|lock| lock.mutex != mutex);
+        if locks.is_empty() {
+            held.remove(&task);
+        }
+    });
+}
+
+// Waiting on a lock the current task holds would never complete.
+#[cfg(debug_assertions)]
+pub(crate) fn check_not_holding(holder: Option<TaskId>, name: Option<&'static str>) {
+    let Some(task) = current_task() else { return };
+    if holder == Some(task) {
+        report(&format!("{} is waiting to lock {}, which it already holds; the lock would never be released", task, name.unwrap_or("a mutex")));
+    }
+}
+
+// With `panic_free` a failed check is logged and the lock goes ahead.
+#[cfg(debug_assertions)]
+fn report(message: &str) {
+    #[cfg(not(feature = "panic_free"))]
+    panic!("{}", message);
+    #[cfg(feature = "panic_free")]
+    crate::console::error(message);
+}
@@ -0,0 +1,179 @@
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use std::sync::Mutex as OsMutex;
+use crate::{Mutex, Pointer};
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+#[derive(Default)]
+struct ReadyQueue {
+    queue: OsMutex<VecDeque<usize>>,
+}
+
+impl ReadyQueue {
+    fn push(&self, task: usize) {
+        let mut queue = self.queue.lock().unwrap_or_else(|error| error.into_inner());
+        if !queue.contains(&task) {
+            queue.push_back(task);
+        }
+    }
+
+    fn pop(&self) -> Option<usize> {
+        self.queue.lock().unwrap_or_else(|error| error.into_inner()).pop_front()
+    }
+}
+
+struct TaskWaker {
+    task: usize,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.push(self.task);
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.push(self.task);
+    }
+}
+
+/// A single-threaded executor that polls woken tasks strictly in the order
+/// they were woken, so every run of a test interleaves its tasks the same way.
+#[derive(Default)]
+pub struct Executor {
+    tasks: Vec<Option<Task>>,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Queues the task to be polled and returns its index, counting from 0
+    /// in spawn order.
+    pub fn spawn(&mut self, future: impl Future<Output = ()> + 'static) -> usize {
+        let task = self.tasks.len();
+        self.tasks.push(Some(Box::pin(future)));
+        self.ready.push(task);
+        task
+    }
+
+    /// Polls the next woken task, returning `false` if none was woken.
+    pub fn step(&mut self) -> bool {
+        let Some(task) = self.ready.pop() else { return false };
+        let Some(slot) = self.tasks.get_mut(task) else { return true };
+        let Some(future) = slot else { return true };
+        let waker = Waker::from(Arc::new(TaskWaker { task, ready: self.ready.clone() }));
+        if future.as_mut().poll(&mut Context::from_waker(&waker)).is_ready() {
+            *slot = None;
+        }
+        true
+    }
+
+    /// Polls woken tasks until none is left, returning how many polls ran.
+    pub fn run_until_stalled(&mut self) -> usize {
+        let mut polls = 0;
+        while self.step() {
+            polls += 1;
+        }
+        polls
+    }
+
+    /// Counts the tasks that have not finished yet.
+    pub fn pending(&self) -> usize {
+        self.tasks.iter().filter(|task| task.is_some()).count()
+    }
+
+    pub fn is_finished(&self, task: usize) -> bool {
+        self.tasks.get(task).is_some_and(Option::is_none)
+    }
+}
+
+/// Returns `Pending` once, waking itself, so the task goes to the back of the
+/// executor's queue.
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Records which contenders took a lock, in the order they took it.
+#[derive(Debug, Clone, Default)]
+pub struct AcquisitionLog {
+    order: Pointer<RefCell<Vec<usize>>>,
+}
+
+impl AcquisitionLog {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn record(&self, id: usize) {
+        (*self.order).borrow_mut().push(id);
+    }
+
+    pub fn order(&self) -> Vec<usize> {
+        (*self.order).borrow().clone()
+    }
+
+    /// Returns a task that locks the mutex, records `id` once it holds the
+    /// lock, and keeps holding it across `hold` yields before releasing it.
+    pub fn contender<T: ?Sized + 'static>(&self, mutex: &Mutex<T>, id: usize, hold: usize) -> impl Future<Output = ()> + 'static {
+        let log = self.clone();
+        let mutex = mutex.clone();
+        async move {
+            let _guard = mutex.lock().await;
+            log.record(id);
+            for _ in 0..hold {
+                yield_now().await;
+            }
+        }
+    }
+
+    /// Spawns contenders `0..count`, polling each once as it is spawned so
+    /// they queue up on the mutex in id order.
+    pub fn spawn_contenders<T: ?Sized + 'static>(&self, executor: &mut Executor, mutex: &Mutex<T>, count: usize, hold: usize) {
+        for id in 0..count {
+            executor.spawn(self.contender(mutex, id, hold));
+            executor.run_until_stalled();
+        }
+    }
+
+    #[track_caller]
+    pub fn assert_order(&self, expected: &[usize]) {
+        let order = self.order();
+        assert_eq!(order, expected, "locks were acquired in order {:?}, expected {:?}", order, expected);
+    }
+
+    /// Asserts that every contender took the lock once, in ascending id order.
+    #[track_caller]
+    pub fn assert_fifo(&self) {
+        let order = self.order();
+        let expected: Vec<_> = (0..order.len()).collect();
+        assert_eq!(order, expected, "locks were not acquired first come, first served: {:?}", order);
+    }
+}
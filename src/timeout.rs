@@ -0,0 +1,50 @@
+use std::task::{Context, Poll};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use gloo_timers::future::TimeoutFuture;
+use crate::{Mutex, MutexRef, LockFuture, TimeoutError};
+use crate::clock::performance_now;
+
+// `setTimeout` fires straight away when given a longer delay
+const MAX_TIMEOUT_MS: u32 = i32::MAX as u32;
+
+impl <T: ?Sized> Mutex<T> {
+    /// Waits for the lock for at most `timeout`, using `setTimeout` to bound
+    /// the wait. Gives up the place in the queue on timeout. Timeouts beyond
+    /// the longest delay `setTimeout` supports (about 24.8 days) are cut
+    /// down to it.
+    pub fn lock_timeout(&self, timeout: Duration) -> LockTimeout<'_, T> {
+        let timeout = timeout.min(Duration::from_millis(MAX_TIMEOUT_MS.into()));
+        LockTimeout {
+            lock: self.lock(),
+            timer: gloo_timers::future::sleep(timeout),
+        }
+    }
+
+    /// Waits for the lock until `performance.now()` reaches `deadline_ms`.
+    /// A deadline that has already passed, or is NaN, still takes the lock if
+    /// it is free.
+    pub fn lock_until(&self, deadline_ms: f64) -> LockTimeout<'_, T> {
+        // `max` maps NaN to zero, and the upper bound keeps an infinite
+        // deadline within what `Duration` and `setTimeout` accept
+        let remaining = (deadline_ms - performance_now()).max(0.0).min(f64::from(MAX_TIMEOUT_MS));
+        self.lock_timeout(Duration::from_secs_f64(remaining / 1000.0))
+    }
+}
+
+pub struct LockTimeout<'a, T: ?Sized> {
+    lock: LockFuture<'a, T>,
+    timer: TimeoutFuture,
+}
+
+impl <'a, T: ?Sized> Future for LockTimeout<'a, T> {
+    type Output = Result<MutexRef<'a, T>, TimeoutError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(guard) = Pin::new(&mut self.lock).poll(cx) {
+            return Poll::Ready(Ok(guard));
+        }
+        Pin::new(&mut self.timer).poll(cx).map(|()| Err(TimeoutError(())))
+    }
+}
@@ -0,0 +1,77 @@
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+use crate::{Mutex, MutexRef, RwLock, RwLockReadRef, RwLockWriteRef};
+
+/// A lock that is awaited for exclusive access, for code that should work
+/// with this crate's `Mutex` as well as other runtimes' mutexes.
+pub trait AsyncLock {
+    type Target: ?Sized;
+    type Guard<'a>: DerefMut<Target = Self::Target> where Self: 'a;
+
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>>;
+
+    fn try_lock(&self) -> Option<Self::Guard<'_>>;
+}
+
+/// Like `AsyncLock`, for locks that also hand out shared read access.
+pub trait AsyncRwLock {
+    type Target: ?Sized;
+    type ReadGuard<'a>: Deref<Target = Self::Target> where Self: 'a;
+    type WriteGuard<'a>: DerefMut<Target = Self::Target> where Self: 'a;
+
+    fn read(&self) -> impl Future<Output = Self::ReadGuard<'_>>;
+
+    fn write(&self) -> impl Future<Output = Self::WriteGuard<'_>>;
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>>;
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>>;
+}
+
+impl <T: ?Sized> AsyncLock for Mutex<T> {
+    type Target = T;
+    type Guard<'a> = MutexRef<'a, T> where Self: 'a;
+
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+        Mutex::lock(self)
+    }
+
+    fn try_lock(&self) -> Option<Self::Guard<'_>> {
+        Mutex::try_lock(self)
+    }
+}
+
+impl <T: ?Sized> AsyncLock for crate::sync::Mutex<T> {
+    type Target = T;
+    type Guard<'a> = crate::sync::MutexRef<'a, T> where Self: 'a;
+
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+        crate::sync::Mutex::lock(self)
+    }
+
+    fn try_lock(&self) -> Option<Self::Guard<'_>> {
+        crate::sync::Mutex::try_lock(self)
+    }
+}
+
+impl <T: ?Sized> AsyncRwLock for RwLock<T> {
+    type Target = T;
+    type ReadGuard<'a> = RwLockReadRef<'a, T> where Self: 'a;
+    type WriteGuard<'a> = RwLockWriteRef<'a, T> where Self: 'a;
+
+    fn read(&self) -> impl Future<Output = Self::ReadGuard<'_>> {
+        RwLock::read(self)
+    }
+
+    fn write(&self) -> impl Future<Output = Self::WriteGuard<'_>> {
+        RwLock::write(self)
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        RwLock::try_read(self)
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        RwLock::try_write(self)
+    }
+}
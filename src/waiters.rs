@@ -0,0 +1,224 @@
+use core::task::Waker;
+use alloc::vec::Vec;
+
+/// Handle to a waiter's slot. The generation tells a slot's current owner
+/// apart from earlier owners whose ids went stale when they were cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WakerId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug)]
+struct Link<K> {
+    waker: Waker,
+    kind: K,
+    prev: Option<u32>,
+    next: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Slot<K> {
+    generation: u32,
+    live: bool,
+    granted: bool,
+    link: Option<Link<K>>,
+    next_free: Option<u32>,
+}
+
+/// FIFO queue of pending futures shared by the crate's primitives.
+///
+/// A waiter is granted by popping it off the front of the queue; the grant is
+/// remembered until the waiter's next poll collects it, or until the waiter is
+/// cancelled and the primitive has to pass the grant on.
+///
+/// Waiters live in a slab linked by index, so registering and cancelling are
+/// O(1). Freed slots are reused, but the slab is not intrusive: it grows
+/// whenever more waiters are queued at once than ever before. Every id handed
+/// out by `next_id` must eventually be passed to `cancel` to free its slot.
+#[derive(Debug)]
+pub(crate) struct Waiters<K = ()> {
+    slots: Vec<Slot<K>>,
+    free: Option<u32>,
+    head: Option<u32>,
+    tail: Option<u32>,
+    len: usize,
+}
+
+impl <K> Default for Waiters<K> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: None,
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+}
+
+impl <K> Waiters<K> {
+    pub(crate) fn next_id(&mut self) -> WakerId {
+        if let Some(index) = self.free {
+            if let Some(slot) = self.slots.get_mut(index as usize) {
+                self.free = slot.next_free.take();
+                slot.live = true;
+                return WakerId { index, generation: slot.generation };
+            }
+        }
+        self.slots.push(Slot { generation: 0, live: true, granted: false, link: None, next_free: None });
+        WakerId { index: (self.slots.len() - 1) as u32, generation: 0 }
+    }
+
+    fn slot_mut(&mut self, id: WakerId) -> Option<&mut Slot<K>> {
+        self.slots.get_mut(id.index as usize).filter(|slot| slot.live && slot.generation == id.generation)
+    }
+
+    pub(crate) fn register(&mut self, id: WakerId, waker: &Waker, kind: K) {
+        let tail = self.tail;
+        self.register_before(id, waker, kind, None, tail);
+    }
+
+    // Queues the waiter between `prev` and `next`, or only updates its waker
+    // if it is already queued. Re-polls with the same waker clone nothing.
+    fn register_before(&mut self, id: WakerId, waker: &Waker, kind: K, next: Option<u32>, prev: Option<u32>) {
+        let Some(slot) = self.slot_mut(id) else { return };
+        if let Some(link) = &mut slot.link {
+            if !link.waker.will_wake(waker) {
+                link.waker = waker.clone();
+            }
+            return;
+        }
+        slot.link = Some(Link { waker: waker.clone(), kind, prev, next });
+
+        self.relink(prev, next, Some(id.index));
+        self.len += 1;
+    }
+
+    // Every index reachable from `head`, `tail` or another link is queued,
+    // so these only come back empty if that bookkeeping is broken.
+    fn link(&self, index: u32) -> Option<&Link<K>> {
+        self.slots.get(index as usize)?.link.as_ref()
+    }
+
+    fn link_mut(&mut self, index: u32) -> Option<&mut Link<K>> {
+        self.slots.get_mut(index as usize)?.link.as_mut()
+    }
+
+    // Points the neighbours `prev` and `next` at `index`, or at each other
+    // when `index` is `None`.
+    fn relink(&mut self, prev: Option<u32>, next: Option<u32>, index: Option<u32>) {
+        match prev {
+            Some(prev) => {
+                if let Some(link) = self.link_mut(prev) {
+                    link.next = index.or(next);
+                }
+            }
+            None => self.head = index.or(next),
+        }
+        match next {
+            Some(next) => {
+                if let Some(link) = self.link_mut(next) {
+                    link.prev = index.or(prev);
+                }
+            }
+            None => self.tail = index.or(prev),
+        }
+    }
+
+    fn unlink(&mut self, index: u32) -> Option<Waker> {
+        let Link { waker, prev, next, .. } = self.slots.get_mut(index as usize)?.link.take()?;
+        self.relink(prev, next, None);
+        self.len -= 1;
+        Some(waker)
+    }
+
+    fn grant(&mut self, index: u32) -> Option<Waker> {
+        let waker = self.unlink(index)?;
+        if let Some(slot) = self.slots.get_mut(index as usize) {
+            slot.granted = true;
+        }
+        Some(waker)
+    }
+
+    pub(crate) fn front(&self) -> Option<&K> {
+        self.link(self.head?).map(|link| &link.kind)
+    }
+
+    pub(crate) fn grant_front(&mut self) -> Option<Waker> {
+        self.grant(self.head?)
+    }
+
+    pub(crate) fn take_grant(&mut self, id: WakerId) -> bool {
+        match self.slot_mut(id) {
+            Some(slot) => core::mem::take(&mut slot.granted),
+            None => false,
+        }
+    }
+
+    /// Removes every queued waiter without granting it anything.
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = Waker> + '_ {
+        core::iter::from_fn(move || {
+            let head = self.head?;
+            self.unlink(head)
+        })
+    }
+
+    /// Forgets the waiter and frees its slot, returning whether it had already
+    /// been granted. Stale ids are ignored.
+    pub(crate) fn cancel(&mut self, id: WakerId) -> bool {
+        let Some(slot) = self.slot_mut(id) else { return false };
+        let granted = core::mem::take(&mut slot.granted);
+        self.unlink(id.index);
+
+        let free = self.free;
+        let Some(slot) = self.slots.get_mut(id.index as usize) else { return granted };
+        slot.live = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.next_free = free;
+        self.free = Some(id.index);
+        granted
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl <K: Ord> Waiters<K> {
+    /// Like `register`, but queues a new waiter ahead of every waiter with a
+    /// lower `kind`, and behind every waiter with an equal or higher one.
+    pub(crate) fn register_ordered(&mut self, id: WakerId, waker: &Waker, kind: K) {
+        let mut next = self.head;
+        while let Some(link) = next.and_then(|index| self.link(index)) {
+            if link.kind < kind {
+                break;
+            }
+            next = link.next;
+        }
+        let prev = match next {
+            Some(next) => self.link(next).and_then(|link| link.prev),
+            None => self.tail,
+        };
+        self.register_before(id, waker, kind, next, prev);
+    }
+}
+
+impl <K: PartialEq> Waiters<K> {
+    /// Like `grant_front`, but grants the most recently queued of the waiters
+    /// that share the front waiter's kind.
+    pub(crate) fn grant_latest(&mut self) -> Option<Waker> {
+        let mut latest = self.head?;
+        while let Some(link) = self.link(latest) {
+            match link.next {
+                Some(next) if self.link(next).is_some_and(|next| next.kind == link.kind) => latest = next,
+                _ => break,
+            }
+        }
+        self.grant(latest)
+    }
+}
@@ -0,0 +1,166 @@
+use core::cell::{Ref, RefCell};
+use core::error::Error;
+use core::fmt;
+use core::task::{Waker, Context, Poll};
+use core::future::Future;
+use core::pin::Pin;
+use alloc::vec::Vec;
+use crate::Pointer;
+use crate::waiters::{WakerId, Waiters};
+
+#[derive(Debug, Default)]
+struct WatchState {
+    version: usize,
+    closed: bool,
+    waiters: Waiters,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    value: RefCell<T>,
+    state: RefCell<WatchState>,
+}
+
+impl <T> Shared<T> {
+    fn notify(&self, f: impl FnOnce(&mut WatchState)) {
+        let wakers: Vec<_> = {
+            let mut state = self.state.borrow_mut();
+            f(&mut state);
+            state.waiters.drain().collect()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl Error for RecvError {}
+
+pub fn channel<T>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Pointer::new(Shared {
+        value: RefCell::new(initial),
+        state: Default::default(),
+    });
+    (Sender { shared: shared.clone() }, Receiver { shared, version: 0 })
+}
+
+#[derive(Debug)]
+pub struct Sender<T> {
+    shared: Pointer<Shared<T>>,
+}
+
+impl <T> Sender<T> {
+    pub fn send(&self, value: T) {
+        self.send_replace(value);
+    }
+
+    pub fn send_replace(&self, value: T) -> T {
+        let old = self.shared.value.replace(value);
+        self.shared.notify(|state| state.version = state.version.wrapping_add(1));
+        old
+    }
+
+    pub fn send_modify(&self, f: impl FnOnce(&mut T)) {
+        f(&mut self.shared.value.borrow_mut());
+        self.shared.notify(|state| state.version = state.version.wrapping_add(1));
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.shared.value.borrow()
+    }
+
+    pub fn subscribe(&self) -> Receiver<T> {
+        let version = self.shared.state.borrow().version;
+        Receiver { shared: self.shared.clone(), version }
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        Pointer::strong_count(&self.shared) - 1
+    }
+}
+
+impl <T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.notify(|state| state.closed = true);
+    }
+}
+
+/// Receivers remember the last version they marked as seen, so every
+/// receiver observes each change independently.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    shared: Pointer<Shared<T>>,
+    version: usize,
+}
+
+impl <T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone(), version: self.version }
+    }
+}
+
+impl <T> Receiver<T> {
+    /// The returned `Ref` must not be held across an await point, otherwise a
+    /// send from another task will panic.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.shared.value.borrow()
+    }
+
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        self.version = self.shared.state.borrow().version;
+        self.shared.value.borrow()
+    }
+
+    pub fn has_changed(&self) -> Result<bool, RecvError> {
+        let state = self.shared.state.borrow();
+        if state.version != self.version {
+            Ok(true)
+        } else if state.closed {
+            Err(RecvError(()))
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub fn changed(&mut self) -> Changed<'_, T> {
+        let waker_id = self.shared.state.borrow_mut().waiters.next_id();
+        Changed { waker_id, receiver: self }
+    }
+}
+
+pub struct Changed<'a, T> {
+    waker_id: WakerId,
+    receiver: &'a mut Receiver<T>,
+}
+
+impl <'a, T> Future for Changed<'a, T> {
+    type Output = Result<(), RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let waker_id = self.waker_id;
+        let receiver = &mut *self.receiver;
+        let mut state = receiver.shared.state.borrow_mut();
+        if state.version != receiver.version {
+            receiver.version = state.version;
+            Poll::Ready(Ok(()))
+        } else if state.closed {
+            Poll::Ready(Err(RecvError(())))
+        } else {
+            state.waiters.register(waker_id, cx.waker(), ());
+            Poll::Pending
+        }
+    }
+}
+
+impl <'a, T> Drop for Changed<'a, T> {
+    fn drop(&mut self) {
+        self.receiver.shared.state.borrow_mut().waiters.cancel(self.waker_id);
+    }
+}
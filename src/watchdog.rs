@@ -0,0 +1,77 @@
+use core::panic::Location;
+use std::format;
+use std::time::Duration;
+use crate::clock::Instant;
+use crate::console;
+
+/// A guard that was held for longer than the threshold given to
+/// `MutexBuilder::warn_after`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LongHold {
+    /// The name given to `MutexBuilder::name`, if any.
+    pub name: Option<&'static str>,
+    /// Where the guard was asked for.
+    pub site: &'static Location<'static>,
+    /// How long the guard had been held when it was reported.
+    pub held: Duration,
+    /// Whether it was reported on release, rather than because another
+    /// future queued up behind it.
+    pub released: bool,
+}
+
+/// The handler used unless `MutexBuilder::on_long_hold` replaces it: a
+/// console warning, or a line on stderr outside the browser.
+pub fn warn_long_hold(hold: &LongHold) {
+    let still = if hold.released { "" } else { ", and is still held" };
+    console::warn(&format!(
+        "wasm_mutex: {} was held for {:.1} ms{}; it was locked at {}",
+        hold.name.unwrap_or("a mutex"),
+        hold.held.as_secs_f64() * 1000.0,
+        still,
+        hold.site,
+    ));
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Watchdog {
+    pub(crate) threshold: Duration,
+    pub(crate) handler: fn(&LongHold),
+}
+
+// Per-mutex bookkeeping for the current hold.
+#[derive(Debug, Default)]
+pub(crate) struct HoldTimer {
+    started: Option<Instant>,
+    reported: bool,
+}
+
+impl HoldTimer {
+    pub(crate) fn start(&mut self) {
+        *self = HoldTimer { started: Some(Instant::now()), reported: false };
+    }
+
+    /// Returns a report once the current hold has gone on for longer than
+    /// the watchdog allows, at most once per hold.
+    pub(crate) fn check(&mut self, watchdog: &Watchdog, name: Option<&'static str>, site: &'static Location<'static>, released: bool) -> Option<Overdue> {
+        let held = self.started?.elapsed();
+        if self.reported || held <= watchdog.threshold {
+            return None;
+        }
+        self.reported = true;
+        Some(Overdue { handler: watchdog.handler, hold: LongHold { name, site, held, released } })
+    }
+}
+
+// A long hold found while the mutex's state was borrowed, to be reported
+// once it no longer is, since the handler may use the mutex.
+pub(crate) struct Overdue {
+    handler: fn(&LongHold),
+    hold: LongHold,
+}
+
+impl Overdue {
+    pub(crate) fn report(self) {
+        (self.handler)(&self.hold);
+    }
+}
@@ -0,0 +1,84 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::Context;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::Barrier;
+
+// the barrier's `Debug` output lists one `Slot` per waiter slot it holds
+fn slots(barrier: &Barrier) -> usize {
+    format!("{:?}", barrier).matches("Slot {").count()
+}
+
+#[test]
+fn last_arrival_leads_and_releases_the_others_in_order() {
+    let mut pool = LocalPool::new();
+    let barrier = Barrier::new(4);
+    let order = Rc::new(RefCell::new(Vec::new()));
+
+    for i in 0..4 {
+        let (barrier, order) = (barrier.clone(), order.clone());
+        pool.spawner().spawn_local(async move {
+            let leader = barrier.wait().await.is_leader();
+            order.borrow_mut().push((i, leader));
+        }).unwrap();
+        pool.run_until_stalled();
+    }
+    pool.run();
+
+    assert_eq!(*order.borrow(), [(3, true), (0, false), (1, false), (2, false)]);
+}
+
+#[test]
+fn reuse_over_many_rounds_keeps_its_slots() {
+    const TASKS: usize = 5;
+    const ROUNDS: usize = 200;
+
+    let mut pool = LocalPool::new();
+    let barrier = Barrier::new(TASKS);
+    let leaders = Rc::new(RefCell::new(0));
+    for _ in 0..TASKS {
+        let (barrier, leaders) = (barrier.clone(), leaders.clone());
+        pool.spawner().spawn_local(async move {
+            for _ in 0..ROUNDS {
+                if barrier.wait().await.is_leader() {
+                    *leaders.borrow_mut() += 1;
+                }
+            }
+        }).unwrap();
+    }
+    pool.run();
+
+    assert_eq!(*leaders.borrow(), ROUNDS);
+    assert!(slots(&barrier) <= TASKS, "{} slots after {} rounds", slots(&barrier), ROUNDS);
+}
+
+#[test]
+fn cancelled_wait_takes_back_its_arrival() {
+    let mut pool = LocalPool::new();
+    let barrier = Barrier::new(2);
+    let waker = futures::task::noop_waker();
+    {
+        let mut wait = pin!(barrier.wait());
+        assert!(wait.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+
+    // the cancelled arrival no longer counts, so the next one still waits
+    let released = Rc::new(RefCell::new(None));
+    pool.spawner().spawn_local({
+        let (barrier, released) = (barrier.clone(), released.clone());
+        async move {
+            *released.borrow_mut() = Some(barrier.wait().await.is_leader());
+        }
+    }).unwrap();
+    pool.run_until_stalled();
+    assert_eq!(*released.borrow(), None);
+
+    assert!(pool.run_until(barrier.wait()).is_leader());
+    pool.run();
+    assert_eq!(*released.borrow(), Some(false));
+    // the cancelled wait's slot was reused rather than leaked
+    assert_eq!(slots(&barrier), 1);
+}
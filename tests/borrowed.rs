@@ -0,0 +1,18 @@
+use futures::executor::block_on;
+use wasm_mutex::{Mutex, MutexRef};
+
+#[test]
+fn locks_a_mutex_over_borrowed_data() {
+    let mut log = Vec::new();
+    {
+        let mutex = Mutex::new(&mut log);
+        block_on(async {
+            mutex.lock().await.push("locked");
+            mutex.with_lock(|log| log.push("with_lock")).await;
+            let guard = MutexRef::wait_until(mutex.lock().await, |log| !log.is_empty()).await;
+            let mut first = MutexRef::map(guard, |log| &mut log[0]);
+            *first = "mapped";
+        });
+    }
+    assert_eq!(log, ["mapped", "with_lock"]);
+}
@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::Context;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::broadcast::{self, Overflow, RecvError, SendError, TryRecvError};
+
+// with the buffer drained, the sender's `Debug` output lists one `Slot` per
+// waiter slot it holds
+fn slots<T: std::fmt::Debug>(tx: &broadcast::Sender<T>) -> usize {
+    format!("{:?}", tx).matches("Slot {").count()
+}
+
+#[test]
+fn every_receiver_sees_every_message_in_order() {
+    let mut pool = LocalPool::new();
+    let (tx, rx) = broadcast::channel(4);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let receivers = vec![rx, tx.subscribe(), tx.subscribe()];
+    for (id, mut rx) in receivers.into_iter().enumerate() {
+        let seen = seen.clone();
+        pool.spawner().spawn_local(async move {
+            while let Ok(value) = rx.recv().await {
+                seen.borrow_mut().push((id, value));
+            }
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+
+    assert_eq!(tx.send(1), Ok(3));
+    pool.run_until_stalled();
+    assert_eq!(tx.send(2), Ok(3));
+    drop(tx);
+    pool.run();
+    assert_eq!(*seen.borrow(), [(0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)]);
+}
+
+#[test]
+fn slow_receiver_lags_when_oldest_is_dropped() {
+    let (tx, mut slow) = broadcast::channel(2);
+    let mut fast = tx.subscribe();
+    for i in 0..5 {
+        tx.send(i).unwrap();
+        assert_eq!(fast.try_recv(), Ok(i));
+    }
+    assert_eq!(slow.try_recv(), Err(TryRecvError::Lagged(3)));
+    assert_eq!(slow.try_recv(), Ok(3));
+    assert_eq!(slow.try_recv(), Ok(4));
+    assert_eq!(slow.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn slow_receiver_holds_back_senders_when_new_is_rejected() {
+    let (tx, mut slow) = broadcast::channel_with(2, Overflow::RejectNew);
+    let mut fast = tx.subscribe();
+    tx.send(0).unwrap();
+    tx.send(1).unwrap();
+    assert_eq!(fast.try_recv(), Ok(0));
+    assert_eq!(fast.try_recv(), Ok(1));
+    assert_eq!(tx.send(2), Err(SendError::Full(2)));
+
+    assert_eq!(slow.try_recv(), Ok(0));
+    assert_eq!(tx.send(2), Ok(2));
+    // dropping the slow receiver gives back what it had not read
+    drop(slow);
+    assert_eq!(tx.send(3), Ok(1));
+    assert_eq!(fast.try_recv(), Ok(2));
+    assert_eq!(fast.try_recv(), Ok(3));
+}
+
+#[test]
+fn dropping_the_senders_closes_after_the_buffer_drains() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = broadcast::channel(4);
+    let other = tx.clone();
+    tx.send(1).unwrap();
+    drop(tx);
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+    let waiting = pool.spawner().spawn_local_with_handle(async move { rx.recv().await }).unwrap();
+    pool.run_until_stalled();
+    drop(other);
+    assert_eq!(pool.run_until(waiting), Err(RecvError::Closed));
+}
+
+#[test]
+fn dropping_every_receiver_fails_sends() {
+    let (tx, rx) = broadcast::channel(4);
+    drop(rx);
+    assert_eq!(tx.receiver_count(), 0);
+    assert_eq!(tx.send(1), Err(SendError::Closed(1)));
+
+    // a new subscriber only sees what is sent after it joined
+    let mut rx = tx.subscribe();
+    assert_eq!(tx.send(2), Ok(1));
+    assert_eq!(rx.try_recv(), Ok(2));
+}
+
+#[test]
+fn cancelled_recv_leaves_its_slot_free() {
+    let (tx, mut rx) = broadcast::channel::<u32>(4);
+    let waker = futures::task::noop_waker();
+    for _ in 0..100 {
+        let mut recv = pin!(rx.recv());
+        assert!(recv.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    assert_eq!(slots(&tx), 1);
+    tx.send(1).unwrap();
+    assert_eq!(rx.try_recv(), Ok(1));
+}
+
+#[test]
+fn channel_is_reusable_over_many_rounds() {
+    const ROUNDS: u32 = 200;
+
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = broadcast::channel_with(1, Overflow::RejectNew);
+    let received = pool.spawner().spawn_local_with_handle(async move {
+        let mut sum = 0;
+        while let Ok(value) = rx.recv().await {
+            sum += value;
+        }
+        sum
+    }).unwrap();
+    for i in 1..=ROUNDS {
+        pool.run_until_stalled();
+        tx.send(i).unwrap();
+    }
+    pool.run_until_stalled();
+    assert!(slots(&tx) <= 1, "{} slots after {} rounds", slots(&tx), ROUNDS);
+    drop(tx);
+    assert_eq!(pool.run_until(received), ROUNDS * (ROUNDS + 1) / 2);
+}
@@ -0,0 +1,89 @@
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{CancellationToken, Mutex};
+
+#[test]
+fn cancelling_a_token_cancels_its_children_but_not_its_parent() {
+    let parent = CancellationToken::new();
+    let child = parent.child_token();
+    let grandchild = child.child_token();
+    let sibling = parent.child_token();
+
+    child.cancel();
+    assert!(child.is_cancelled() && grandchild.is_cancelled());
+    assert!(!parent.is_cancelled() && !sibling.is_cancelled());
+
+    parent.cancel();
+    assert!(sibling.is_cancelled());
+    assert!(parent.child_token().is_cancelled());
+}
+
+#[test]
+fn cancel_wakes_waiting_futures() {
+    let mut pool = LocalPool::new();
+    let token = CancellationToken::new();
+    let child = token.child_token();
+    pool.spawner().spawn_local(async move { child.cancelled().await }).unwrap();
+    pool.run_until_stalled();
+
+    token.cancel();
+    pool.run();
+}
+
+#[test]
+fn lock_or_cancelled_gives_up_its_place_when_cancelled() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(0);
+    let token = CancellationToken::new();
+
+    let held = mutex.try_lock().unwrap();
+    let (waiter, waiter_token) = (mutex.clone(), token.clone());
+    let result = pool.spawner().spawn_local_with_handle(async move {
+        waiter.lock_or_cancelled(&waiter_token).await.map(|guard| *guard)
+    }).unwrap();
+    pool.run_until_stalled();
+
+    token.cancel();
+    assert!(pool.run_until(result).is_err());
+    drop(held);
+    assert!(mutex.try_lock().is_some());
+    assert!(pool.run_until(mutex.lock_or_cancelled(&token)).is_err());
+}
+
+#[test]
+fn lock_or_cancelled_takes_the_lock_before_cancellation() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(7);
+    let token = CancellationToken::new();
+    assert_eq!(*pool.run_until(mutex.lock_or_cancelled(&token)).unwrap(), 7);
+}
+
+#[test]
+fn dropping_a_token_leaves_its_children_under_its_parent() {
+    let root = CancellationToken::new();
+    let child = root.child_token();
+    let grandchild = child.child_token();
+    let great_grandchild = grandchild.child_token();
+    drop(child);
+    drop(grandchild);
+
+    root.cancel();
+    assert!(great_grandchild.is_cancelled());
+}
+
+#[test]
+fn dropped_children_are_let_go() {
+    let root = CancellationToken::new();
+    for _ in 0..100 {
+        let child = root.child_token();
+        drop(child.clone());
+        drop(child);
+    }
+    // the root's own state and one per child it still holds
+    let nodes = |token: &CancellationToken| format!("{:?}", token).matches("TokenState").count();
+    assert_eq!(nodes(&root), 1);
+    let kept = root.child_token();
+    assert_eq!(nodes(&root), 2);
+    root.cancel();
+    assert!(kept.is_cancelled());
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "chrome_trace")]
+
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{Mutex, task_scope, start_trace, stop_trace};
+
+#[test]
+fn trace_records_waits_and_holds_per_task() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::builder().name("session_state").build(());
+
+    start_trace();
+    let held = mutex.try_lock().unwrap();
+    let waiter = task_scope({
+        let mutex = mutex.clone();
+        async move {
+            drop(mutex.lock().await);
+        }
+    });
+    let task = waiter.id();
+    pool.spawner().spawn_local(waiter).unwrap();
+    pool.run_until_stalled();
+    drop(held);
+    pool.run();
+    let trace = stop_trace();
+
+    assert!(trace.starts_with("{\"traceEvents\":["), "{}", trace);
+    assert_eq!(trace.matches("\"name\":\"session_state lock held\"").count(), 2, "{}", trace);
+    assert_eq!(trace.matches("\"name\":\"session_state lock wait\"").count(), 1, "{}", trace);
+    assert!(trace.contains("\"args\":{\"name\":\"unscoped\"}"), "{}", trace);
+    assert!(trace.contains(&format!("\"args\":{{\"name\":\"{}\"}}", task)), "{}", trace);
+}
+
+#[test]
+fn nothing_is_recorded_outside_a_trace() {
+    let mutex = Mutex::new(());
+    drop(mutex.try_lock().unwrap());
+    assert_eq!(stop_trace(), "{\"traceEvents\":[]}");
+}
@@ -0,0 +1,70 @@
+use futures::executor::{block_on, LocalPool};
+use futures::task::LocalSpawnExt;
+use wasm_mutex::Mutex;
+
+#[test]
+fn clone_shares_the_value() {
+    let mutex = Mutex::new(1);
+    let handle = mutex.clone();
+
+    *handle.try_lock().unwrap() = 2;
+
+    assert_eq!(*mutex.try_lock().unwrap(), 2);
+    assert!(mutex.ptr_eq(&handle));
+}
+
+#[test]
+fn clone_shares_the_lock() {
+    let mutex = Mutex::new(());
+    let handle = mutex.clone();
+
+    let guard = mutex.try_lock().unwrap();
+    assert!(handle.try_lock().is_none());
+    assert!(handle.is_locked());
+    drop(guard);
+
+    assert!(handle.try_lock().is_some());
+}
+
+#[test]
+fn clone_value_forks_the_value() {
+    let mutex = Mutex::new(vec![1]);
+    let fork = block_on(mutex.clone_value());
+
+    fork.try_lock().unwrap().push(2);
+
+    assert_eq!(*mutex.try_lock().unwrap(), [1]);
+    assert_eq!(*fork.try_lock().unwrap(), [1, 2]);
+    assert!(!mutex.ptr_eq(&fork));
+}
+
+#[test]
+fn clone_value_waits_for_the_lock() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(1);
+    let fork = Mutex::new(0);
+
+    let mut guard = mutex.try_lock().unwrap();
+    {
+        let mutex = mutex.clone();
+        let fork = fork.clone();
+        pool.spawner().spawn_local(async move {
+            let value = *mutex.clone_value().await.try_lock().unwrap();
+            *fork.try_lock().unwrap() = value;
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+    *guard = 2;
+    drop(guard);
+    pool.run();
+
+    assert_eq!(*fork.try_lock().unwrap(), 2);
+}
+
+#[test]
+fn clones_share_the_name() {
+    let mutex = Mutex::builder().name("session_state").build(1);
+    assert_eq!(mutex.clone().name(), Some("session_state"));
+    assert_eq!(format!("{:?}", mutex), r#"Mutex { name: "session_state", value: 1 }"#);
+    assert_eq!(Mutex::new(1).name(), None);
+}
@@ -0,0 +1,38 @@
+use futures::executor::block_on;
+use wasm_mutex::compat::futures::{Mutex, MutexGuard, OwnedMutexGuard};
+
+#[test]
+fn futures_names_resolve_to_the_crate_types() {
+    let mutex = Mutex::new((1, 2));
+    block_on(async {
+        let guard: MutexGuard<'_, (i32, i32)> = mutex.lock().await;
+        let mut second = MutexGuard::map(guard, |pair| &mut pair.1);
+        *second += 1;
+        drop(second);
+
+        let owned: OwnedMutexGuard<(i32, i32)> = mutex.lock_owned().await;
+        assert_eq!(*owned, (1, 3));
+    });
+    assert!(mutex.downgrade().upgrade().is_some());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn generic_code_accepts_tokio_and_crate_locks() {
+    use wasm_mutex::AsyncLock;
+
+    async fn bump<L: AsyncLock<Target = u32>>(lock: &L) -> u32 {
+        let mut guard = lock.lock().await;
+        *guard += 1;
+        *guard
+    }
+
+    let ours = wasm_mutex::compat::tokio::Mutex::new(0);
+    let theirs = tokio::sync::Mutex::new(10);
+    block_on(async {
+        assert_eq!(bump(&ours).await, 1);
+        assert_eq!(bump(&theirs).await, 11);
+    });
+    let _held = AsyncLock::try_lock(&theirs).unwrap();
+    assert!(AsyncLock::try_lock(&theirs).is_none());
+}
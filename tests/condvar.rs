@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{Condvar, Mutex};
+
+// Returns `Pending` once, waking itself.
+async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }).await
+}
+
+// the condvar's `Debug` output lists one `Slot` per waiter slot it holds
+fn slots(condvar: &Condvar) -> usize {
+    format!("{:?}", condvar).matches("Slot {").count()
+}
+
+fn spawn_waiter(pool: &LocalPool, mutex: &Mutex<Vec<usize>>, condvar: &Condvar, id: usize) {
+    let (mutex, condvar) = (mutex.clone(), condvar.clone());
+    pool.spawner().spawn_local(async move {
+        let guard = mutex.lock().await;
+        condvar.wait(guard).await.push(id);
+    }).unwrap();
+}
+
+#[test]
+fn waiting_releases_the_guard_and_notify_one_wakes_in_order() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(Vec::new());
+    let condvar = Condvar::new();
+    for id in 0..3 {
+        spawn_waiter(&pool, &mutex, &condvar, id);
+        pool.run_until_stalled();
+    }
+    // every waiter gave its guard back while it waits
+    assert!(mutex.try_lock().is_some());
+
+    condvar.notify_one();
+    pool.run_until_stalled();
+    assert_eq!(*mutex.try_lock().unwrap(), [0]);
+    condvar.notify_one();
+    condvar.notify_one();
+    pool.run();
+    assert_eq!(*mutex.try_lock().unwrap(), [0, 1, 2]);
+}
+
+#[test]
+fn notify_all_wakes_every_waiter_and_stores_nothing() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(Vec::new());
+    let condvar = Condvar::new();
+    condvar.notify_all();
+    for id in 0..3 {
+        spawn_waiter(&pool, &mutex, &condvar, id);
+        pool.run_until_stalled();
+    }
+    assert!(mutex.try_lock().unwrap().is_empty());
+
+    condvar.notify_all();
+    pool.run();
+    assert_eq!(*mutex.try_lock().unwrap(), [0, 1, 2]);
+}
+
+#[test]
+fn notification_taken_by_a_cancelled_wait_goes_to_the_next_waiter() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(Vec::new());
+    let condvar = Condvar::new();
+    let waker = futures::task::noop_waker();
+
+    let guard = mutex.try_lock().unwrap();
+    let mut cancelled = Box::pin(condvar.wait(guard));
+    assert!(cancelled.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    spawn_waiter(&pool, &mutex, &condvar, 1);
+    pool.run_until_stalled();
+
+    condvar.notify_one();
+    drop(cancelled);
+    pool.run();
+    assert_eq!(*mutex.try_lock().unwrap(), [1]);
+
+    // a wait cancelled before any notification leaves nothing behind
+    {
+        let guard = mutex.try_lock().unwrap();
+        let mut cancelled = pin!(condvar.wait(guard));
+        assert!(cancelled.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    assert!(mutex.try_lock().is_some());
+    assert_eq!(slots(&condvar), 2);
+}
+
+#[test]
+fn producer_and_consumer_over_many_rounds() {
+    const ROUNDS: usize = 200;
+
+    let mut pool = LocalPool::new();
+    let queue = Mutex::new(Vec::new());
+    let condvar = Condvar::new();
+    let consumed = Rc::new(RefCell::new(Vec::new()));
+
+    pool.spawner().spawn_local({
+        let (queue, condvar, consumed) = (queue.clone(), condvar.clone(), consumed.clone());
+        async move {
+            while consumed.borrow().len() < ROUNDS {
+                let mut guard = queue.lock().await;
+                while guard.is_empty() {
+                    guard = condvar.wait(guard).await;
+                }
+                consumed.borrow_mut().append(&mut guard);
+            }
+        }
+    }).unwrap();
+    pool.spawner().spawn_local({
+        let (queue, condvar) = (queue.clone(), condvar.clone());
+        async move {
+            for i in 0..ROUNDS {
+                queue.lock().await.push(i);
+                condvar.notify_one();
+                yield_now().await;
+            }
+        }
+    }).unwrap();
+    pool.run();
+
+    assert_eq!(*consumed.borrow(), (0..ROUNDS).collect::<Vec<_>>());
+    assert!(slots(&condvar) <= 1, "{} slots after {} rounds", slots(&condvar), ROUNDS);
+}
@@ -0,0 +1,139 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::{waker, ArcWake, LocalSpawnExt};
+use wasm_mutex::{Fairness, Mutex};
+
+struct CountingWaker(AtomicUsize);
+
+impl ArcWake for CountingWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn repolling_updates_the_waker_in_place() {
+    let mutex = Mutex::new(0);
+    let held = mutex.try_lock().unwrap();
+
+    let stale = Arc::new(CountingWaker(AtomicUsize::new(0)));
+    let fresh = Arc::new(CountingWaker(AtomicUsize::new(0)));
+    let mut lock = pin!(mutex.lock());
+
+    for _ in 0..10 {
+        let waker = waker(stale.clone());
+        assert!(lock.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    let waker = waker(fresh.clone());
+    assert!(lock.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    assert_eq!(mutex.waiter_count(), 1);
+
+    drop(held);
+    assert_eq!(stale.0.load(Ordering::SeqCst), 0);
+    assert_eq!(fresh.0.load(Ordering::SeqCst), 1);
+    assert!(matches!(lock.as_mut().poll(&mut Context::from_waker(&waker)), Poll::Ready(_)));
+    assert_eq!(mutex.waiter_count(), 0);
+}
+
+#[test]
+fn cancelled_waiters_leave_nothing_queued() {
+    let mutex = Mutex::new(0);
+    let held = mutex.try_lock().unwrap();
+
+    let waker = futures::task::noop_waker();
+    for _ in 0..100 {
+        let mut lock = pin!(mutex.lock());
+        assert!(lock.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    assert_eq!(mutex.waiter_count(), 0);
+
+    drop(held);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn heavy_contention_wakes_every_task_once() {
+    const TASKS: usize = 50;
+    const ROUNDS: usize = 20;
+
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+    let mutex = Mutex::new(0);
+
+    let held = mutex.try_lock().unwrap();
+    for _ in 0..TASKS {
+        let mutex = mutex.clone();
+        spawner.spawn_local(async move {
+            for _ in 0..ROUNDS {
+                let mut guard = mutex.lock().await;
+                assert!(mutex.waiter_count() < TASKS);
+                *guard += 1;
+            }
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+    assert_eq!(mutex.waiter_count(), TASKS);
+
+    drop(held);
+    pool.run();
+
+    assert_eq!(mutex.waiter_count(), 0);
+    assert!(!mutex.is_locked());
+    assert_eq!(*mutex.try_lock().unwrap(), TASKS * ROUNDS);
+}
+
+#[test]
+fn interleaved_cancellation_keeps_the_queue_consistent() {
+    let mutex = Mutex::new(Vec::new());
+    let held = mutex.try_lock().unwrap();
+
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut locks: Vec<_> = (0..10).map(|_| Box::pin(mutex.lock())).collect();
+    for lock in &mut locks {
+        assert!(lock.as_mut().poll(&mut cx).is_pending());
+    }
+
+    // drop every other waiter, front, middle and back included
+    let mut kept: Vec<_> = locks.into_iter().enumerate().filter(|(i, _)| i % 2 == 0).collect();
+    assert_eq!(mutex.waiter_count(), 5);
+
+    drop(held);
+    for (i, lock) in &mut kept {
+        let Poll::Ready(mut guard) = lock.as_mut().poll(&mut cx) else { panic!("waiter {i} was skipped") };
+        guard.push(*i);
+    }
+    assert_eq!(mutex.waiter_count(), 0);
+    assert_eq!(*mutex.try_lock().unwrap(), [0, 2, 4, 6, 8]);
+}
+
+#[test]
+fn dropping_the_woken_waiter_passes_the_lock_on() {
+    for fairness in [Fairness::Fifo, Fairness::Lifo, Fairness::Unfair] {
+        let mutex = Mutex::builder().fairness(fairness).build(0);
+        let held = mutex.try_lock().unwrap();
+
+        let counters = [0, 1].map(|_| Arc::new(CountingWaker(AtomicUsize::new(0))));
+        let wakers = counters.clone().map(waker);
+        let mut locks = [Some(Box::pin(mutex.lock())), Some(Box::pin(mutex.lock()))];
+        for (lock, waker) in locks.iter_mut().zip(&wakers) {
+            let lock = lock.as_mut().unwrap();
+            assert!(lock.as_mut().poll(&mut Context::from_waker(waker)).is_pending());
+        }
+
+        drop(held);
+        let woken = counters.iter().position(|counter| counter.0.load(Ordering::SeqCst) == 1).unwrap();
+        let other = 1 - woken;
+        assert_eq!(counters[other].0.load(Ordering::SeqCst), 0, "{fairness:?}");
+
+        // the woken waiter gives up before it gets to run
+        locks[woken] = None;
+        assert_eq!(counters[other].0.load(Ordering::SeqCst), 1, "{fairness:?}");
+        let lock = locks[other].as_mut().unwrap();
+        assert!(lock.as_mut().poll(&mut Context::from_waker(&wakers[other])).is_ready(), "{fairness:?}");
+    }
+}
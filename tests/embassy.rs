@@ -0,0 +1,20 @@
+#![cfg(feature = "embassy")]
+
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use futures::executor::block_on;
+use std::cell::RefCell;
+use wasm_mutex::RawLocalMutex;
+
+#[test]
+fn embassy_primitives_run_on_the_local_raw_mutex() {
+    let counter: BlockingMutex<RawLocalMutex, RefCell<u32>> = BlockingMutex::new(RefCell::new(0));
+    counter.lock(|value| *value.borrow_mut() += 1);
+    assert_eq!(counter.lock(|value| *value.borrow()), 1);
+
+    let mutex: embassy_sync::mutex::Mutex<RawLocalMutex, u32> = embassy_sync::mutex::Mutex::new(1);
+    block_on(async {
+        *mutex.lock().await += 1;
+        assert!(mutex.try_lock().is_ok());
+        assert_eq!(*mutex.lock().await, 2);
+    });
+}
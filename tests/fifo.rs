@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::Mutex;
+
+#[test]
+fn waiters_acquire_in_arrival_order() {
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+    let mutex = Mutex::new(Vec::new());
+
+    let held = mutex.try_lock().unwrap();
+    for i in 0..100 {
+        let mutex = mutex.clone();
+        spawner.spawn_local(async move {
+            mutex.lock().await.push(i);
+        }).unwrap();
+        // poll the new task so it queues up behind the previous ones
+        pool.run_until_stalled();
+    }
+    drop(held);
+    pool.run();
+
+    assert_eq!(*mutex.try_lock().unwrap(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn contended_relocks_are_served_round_robin() {
+    const TASKS: usize = 20;
+    const ROUNDS: usize = 50;
+
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+    let mutex = Mutex::new(Vec::new());
+
+    let held = mutex.try_lock().unwrap();
+    for i in 0..TASKS {
+        let mutex = mutex.clone();
+        spawner.spawn_local(async move {
+            for _ in 0..ROUNDS {
+                mutex.lock().await.push(i);
+            }
+        }).unwrap();
+        pool.run_until_stalled();
+    }
+    drop(held);
+    pool.run();
+
+    let expected: Vec<_> = (0..ROUNDS).flat_map(|_| 0..TASKS).collect();
+    assert_eq!(*mutex.try_lock().unwrap(), expected);
+}
+
+#[test]
+fn released_lock_is_handed_to_waiter_before_newcomers() {
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+    let mutex = Mutex::new(());
+    let order = Rc::new(RefCell::new(Vec::new()));
+
+    let held = mutex.try_lock().unwrap();
+    {
+        let mutex = mutex.clone();
+        let order = order.clone();
+        spawner.spawn_local(async move {
+            let _guard = mutex.lock().await;
+            order.borrow_mut().push("waiter");
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+    drop(held);
+
+    assert!(mutex.try_lock().is_none());
+    {
+        let mutex = mutex.clone();
+        let order = order.clone();
+        spawner.spawn_local(async move {
+            let _guard = mutex.lock().await;
+            order.borrow_mut().push("newcomer");
+        }).unwrap();
+    }
+    pool.run();
+
+    assert_eq!(*order.borrow(), ["waiter", "newcomer"]);
+}
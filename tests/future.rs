@@ -0,0 +1,59 @@
+use futures::executor::block_on;
+use futures::future::FusedFuture;
+use wasm_mutex::Mutex;
+
+#[test]
+fn select_skips_a_completed_lock() {
+    let mutex = Mutex::new(0);
+    let other = Mutex::new(0);
+    let mut lock = mutex.lock();
+    let held = other.try_lock().unwrap();
+    let mut blocked = other.lock();
+
+    block_on(async {
+        futures::select! {
+            mut guard = lock => *guard += 1,
+            _ = blocked => unreachable!(),
+        }
+    });
+    assert!(lock.is_terminated());
+    assert!(!blocked.is_terminated());
+    drop(held);
+    assert_eq!(*mutex.try_lock().unwrap(), 1);
+}
+
+#[test]
+#[cfg(not(feature = "panic_free"))]
+#[should_panic(expected = "polled after completion")]
+fn polling_after_completion_panics() {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::Context;
+    use futures::task::noop_waker;
+
+    let mutex = Mutex::new(0);
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut lock = mutex.lock();
+    assert!(Pin::new(&mut lock).poll(&mut cx).is_ready());
+    let _ = Pin::new(&mut lock).poll(&mut cx);
+}
+
+#[test]
+fn lock_stream_yields_a_guard_per_item() {
+    use futures::StreamExt;
+
+    let mutex = Mutex::new(vec![1, 2, 3]);
+    let mut popped = Vec::new();
+    block_on(async {
+        let mut guards = mutex.lock_stream();
+        while let Some(mut queue) = guards.next().await {
+            match queue.pop() {
+                Some(item) => popped.push(item),
+                None => break,
+            }
+        }
+    });
+    assert_eq!(popped, [3, 2, 1]);
+    assert!(!mutex.is_locked());
+}
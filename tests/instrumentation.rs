@@ -0,0 +1,84 @@
+#![cfg(feature = "instrumentation")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{Mutex, LockEvent, LockInstrumentation, set_instrumentation, clear_instrumentation};
+
+struct Recorder(Rc<RefCell<Vec<String>>>);
+
+impl LockInstrumentation for Recorder {
+    fn on_lock_attempt(&self, lock: &LockEvent) {
+        self.0.borrow_mut().push(format!("attempt {}", lock.name.unwrap()));
+    }
+
+    fn on_contended(&self, lock: &LockEvent) {
+        self.0.borrow_mut().push(format!("contended {}", lock.name.unwrap()));
+    }
+
+    fn on_acquired(&self, lock: &LockEvent, contended: bool) {
+        self.0.borrow_mut().push(format!("acquired {} {}", lock.name.unwrap(), contended));
+    }
+
+    fn on_released(&self, lock: &LockEvent) {
+        self.0.borrow_mut().push(format!("released {}", lock.name.unwrap()));
+    }
+}
+
+#[test]
+fn hooks_see_each_step_of_a_contended_lock() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    set_instrumentation(Recorder(events.clone()));
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::builder().name("session_state").build(());
+
+    let held = mutex.try_lock().unwrap();
+    let waiter = mutex.clone();
+    pool.spawner().spawn_local(async move {
+        drop(waiter.lock().await);
+    }).unwrap();
+    pool.run_until_stalled();
+    assert!(mutex.try_lock().is_none());
+    drop(held);
+    pool.run();
+
+    assert_eq!(*events.borrow(), [
+        "attempt session_state",
+        "acquired session_state false",
+        "attempt session_state",
+        "contended session_state",
+        "attempt session_state",
+        "released session_state",
+        "acquired session_state true",
+        "released session_state",
+    ]);
+
+    clear_instrumentation();
+    drop(mutex.try_lock().unwrap());
+    assert_eq!(events.borrow().len(), 8);
+}
+
+#[test]
+fn lock_handed_to_a_cancelled_future_is_not_reported_as_released() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::builder().name("session_state").build(());
+
+    let held = mutex.try_lock().unwrap();
+    let waiter = mutex.clone();
+    let cancelled = pool.spawner().spawn_local_with_handle(async move {
+        drop(waiter.lock().await);
+    }).unwrap();
+    pool.run_until_stalled();
+    set_instrumentation(Recorder(events.clone()));
+    // the lock is handed to the queued future, which goes away before
+    // taking it
+    drop(held);
+    drop(cancelled);
+    pool.run();
+    clear_instrumentation();
+
+    assert_eq!(*events.borrow(), ["released session_state"]);
+    assert!(mutex.try_lock().is_some());
+}
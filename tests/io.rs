@@ -0,0 +1,100 @@
+#![cfg(feature = "io")]
+
+use std::cell::RefCell;
+use std::io;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use futures::executor::LocalPool;
+use futures::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt, Cursor};
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{GuardedIo, Mutex};
+
+#[test]
+fn writers_share_one_sink() {
+    let mut pool = LocalPool::new();
+    let sink = Mutex::new(Vec::new());
+
+    let held = sink.try_lock().unwrap();
+    for line in [&b"first\n"[..], b"second\n"] {
+        let mut writer = GuardedIo::new(sink.clone());
+        pool.spawner().spawn_local(async move {
+            writer.write_all(line).await.unwrap();
+            writer.flush().await.unwrap();
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+    assert!(sink.has_waiters());
+    drop(held);
+    pool.run();
+
+    assert_eq!(*sink.try_lock().unwrap(), b"first\nsecond\n");
+}
+
+#[test]
+fn readers_share_one_source() {
+    let source = Mutex::new(Cursor::new(b"abcdef".to_vec()));
+    let mut a = GuardedIo::new(source.clone());
+    let mut b = GuardedIo::new(source.clone());
+
+    futures::executor::block_on(async {
+        let mut buf = [0; 3];
+        a.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"abc");
+        b.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"def");
+    });
+    assert!(!source.is_locked());
+}
+
+// Refuses every other write, parking the writing task until the test wakes it.
+struct Flaky {
+    written: Vec<u8>,
+    refuse: bool,
+    parked: Rc<RefCell<Option<Waker>>>,
+}
+
+impl AsyncWrite for Flaky {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.refuse = !self.refuse;
+        if self.refuse {
+            *self.parked.borrow_mut() = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        self.written.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn pending_write_keeps_the_lock_until_it_completes() {
+    let mut pool = LocalPool::new();
+    let parked = Rc::new(RefCell::new(None));
+    let writer = Mutex::new(Flaky { written: Vec::new(), refuse: false, parked: parked.clone() });
+    for line in [&b"first\n"[..], b"second\n"] {
+        let mut io = GuardedIo::new(writer.clone());
+        pool.spawner().spawn_local(async move {
+            io.write_all(line).await.unwrap();
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+    // the first task is parked on the writer, the second on the lock
+    assert!(writer.is_locked());
+    assert_eq!(writer.waiter_count(), 1);
+
+    for _ in 0..2 {
+        let waker = parked.borrow_mut().take().unwrap();
+        waker.wake();
+        pool.run_until_stalled();
+    }
+    assert!(!writer.is_locked());
+    assert_eq!(writer.try_lock().unwrap().written, b"first\nsecond\n");
+}
@@ -0,0 +1,71 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::Lazy;
+
+// Returns `Pending` once, waking itself.
+async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }).await
+}
+
+#[test]
+fn initializer_runs_once_for_every_caller() {
+    let mut pool = LocalPool::new();
+    let runs = Rc::new(RefCell::new(0));
+    let lazy = Lazy::new({
+        let runs = runs.clone();
+        async move {
+            *runs.borrow_mut() += 1;
+            yield_now().await;
+            42
+        }
+    });
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    for _ in 0..3 {
+        let (lazy, seen) = (lazy.clone(), seen.clone());
+        pool.spawner().spawn_local(async move {
+            let value = *lazy.get().await;
+            seen.borrow_mut().push(value);
+        }).unwrap();
+    }
+    pool.run();
+
+    assert_eq!(*runs.borrow(), 1);
+    assert_eq!(*seen.borrow(), [42, 42, 42]);
+    assert_eq!(pool.run_until(lazy.get()), &42);
+}
+
+#[test]
+fn cancelled_caller_leaves_the_initializer_for_the_next_one() {
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    let lazy = Lazy::new({
+        let steps = steps.clone();
+        async move {
+            steps.borrow_mut().push("started");
+            yield_now().await;
+            steps.borrow_mut().push("resumed");
+            1
+        }
+    });
+
+    let waker = futures::task::noop_waker();
+    for _ in 0..10 {
+        let mut get = pin!(lazy.get());
+        let _ = get.as_mut().poll(&mut Context::from_waker(&waker));
+    }
+    // the second call carried on where the cancelled one stopped
+    assert_eq!(*steps.borrow(), ["started", "resumed"]);
+    assert_eq!(LocalPool::new().run_until(lazy.get()), &1);
+}
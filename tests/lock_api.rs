@@ -0,0 +1,30 @@
+#![cfg(feature = "lock_api")]
+
+use wasm_mutex::{LocalMutex, LocalMutexGuard};
+
+#[test]
+fn local_mutex_maps_and_releases() {
+    thread_local! {
+        static VALUES: LocalMutex<Vec<u32>> = const { LocalMutex::new(Vec::new()) };
+    }
+
+    VALUES.with(|mutex| {
+        mutex.lock().extend([1, 2, 3]);
+        {
+            let mut first = LocalMutexGuard::map(mutex.lock(), |values| &mut values[0]);
+            *first = 10;
+            assert!(mutex.try_lock().is_none());
+        }
+        assert!(!mutex.is_locked());
+        assert_eq!(*mutex.lock(), [10, 2, 3]);
+    });
+}
+
+#[test]
+#[cfg(not(feature = "panic_free"))]
+#[should_panic(expected = "would deadlock")]
+fn relocking_a_held_local_mutex_panics() {
+    let mutex = LocalMutex::new(0);
+    let _held = mutex.lock();
+    let _again = mutex.lock();
+}
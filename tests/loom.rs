@@ -0,0 +1,62 @@
+#![cfg(loom)]
+
+// Run with `RUSTFLAGS="--cfg loom" cargo test --test loom --release`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use futures::task::noop_waker;
+use loom::future::block_on;
+use loom::thread;
+use wasm_mutex::sync::Mutex;
+
+#[test]
+fn contended_locks_all_get_through() {
+    loom::model(|| {
+        let mutex = Mutex::new(0);
+        let other = mutex.clone();
+        let handle = thread::spawn(move || block_on(async move {
+            *other.lock().await += 1;
+        }));
+        block_on(async {
+            *mutex.lock().await += 1;
+        });
+        handle.join().unwrap();
+
+        assert_eq!(*mutex.try_lock().unwrap(), 2);
+        assert_eq!(mutex.waiter_count(), 0);
+    });
+}
+
+#[test]
+fn waiter_dropped_during_release_passes_the_lock_on() {
+    loom::model(|| {
+        let mutex = Mutex::new(());
+        let guard = mutex.try_lock_owned().unwrap();
+        let releaser = thread::spawn(move || drop(guard));
+
+        // queue up and give up while the release may be handing us the lock
+        let waker = noop_waker();
+        let mut lock = mutex.lock();
+        let _ = Pin::new(&mut lock).poll(&mut Context::from_waker(&waker));
+        drop(lock);
+        releaser.join().unwrap();
+
+        assert!(!mutex.is_locked());
+        assert_eq!(mutex.waiter_count(), 0);
+    });
+}
+
+#[test]
+fn try_lock_races_release() {
+    loom::model(|| {
+        let mutex = Mutex::new(0);
+        let guard = mutex.try_lock_owned().unwrap();
+        let other = mutex.clone();
+        let racer = thread::spawn(move || other.try_lock().map(|mut guard| *guard += 1).is_some());
+        drop(guard);
+        let raced = racer.join().unwrap();
+
+        assert_eq!(*mutex.try_lock().unwrap(), raced as i32);
+    });
+}
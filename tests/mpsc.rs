@@ -0,0 +1,151 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::mpsc::{channel, unbounded_channel, SendError, TryRecvError, TrySendError};
+
+#[test]
+fn senders_wait_at_capacity_and_are_let_in_in_order() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = channel(2);
+    tx.try_send(0).unwrap();
+    tx.try_send(1).unwrap();
+    assert_eq!(tx.try_send(9), Err(TrySendError::Full(9)));
+
+    let sent = Rc::new(RefCell::new(Vec::new()));
+    for i in 2..5 {
+        let (tx, sent) = (tx.clone(), sent.clone());
+        pool.spawner().spawn_local(async move {
+            tx.send(i).await.unwrap();
+            sent.borrow_mut().push(i);
+        }).unwrap();
+        pool.run_until_stalled();
+    }
+    assert!(sent.borrow().is_empty());
+    // waiting senders are ahead of `try_send`, even once there is room
+    assert_eq!(rx.try_recv(), Ok(0));
+    assert_eq!(tx.try_send(9), Err(TrySendError::Full(9)));
+
+    pool.run_until_stalled();
+    assert_eq!(*sent.borrow(), [2]);
+    let mut received = vec![rx.try_recv().unwrap()];
+    while let Ok(value) = rx.try_recv() {
+        received.push(value);
+        pool.run_until_stalled();
+    }
+    assert_eq!(received, [1, 2, 3, 4]);
+    assert_eq!(*sent.borrow(), [2, 3, 4]);
+}
+
+#[test]
+fn cancelled_send_gives_back_its_reserved_slot() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = channel(1);
+    tx.try_send(0).unwrap();
+
+    let waker = futures::task::noop_waker();
+    let mut cancelled = Box::pin(tx.send(1));
+    assert!(cancelled.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    let second = tx.clone();
+    let queued = pool.spawner().spawn_local_with_handle(async move { second.send(2).await }).unwrap();
+    pool.run_until_stalled();
+
+    // the slot freed here is reserved for the first waiting sender, which
+    // hands it on to the next one when it is dropped without sending
+    assert_eq!(rx.try_recv(), Ok(0));
+    drop(cancelled);
+    assert_eq!(pool.run_until(queued), Ok(()));
+    assert_eq!(rx.try_recv(), Ok(2));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn receiver_sees_the_end_once_the_last_sender_drops() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = channel(4);
+    let other = tx.clone();
+    tx.try_send(1).unwrap();
+    drop(tx);
+
+    let received = pool.spawner().spawn_local_with_handle(async move {
+        let mut received = Vec::new();
+        while let Some(value) = rx.recv().await {
+            received.push(value);
+        }
+        received
+    }).unwrap();
+    pool.run_until_stalled();
+    other.try_send(2).unwrap();
+    pool.run_until_stalled();
+    drop(other);
+    assert_eq!(pool.run_until(received), [1, 2]);
+}
+
+#[test]
+fn close_fails_new_sends_and_drains_what_was_queued() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = channel(1);
+    tx.try_send(1).unwrap();
+    let blocked = pool.spawner().spawn_local_with_handle({
+        let tx = tx.clone();
+        async move { tx.send(2).await }
+    }).unwrap();
+    pool.run_until_stalled();
+
+    rx.close();
+    assert!(tx.is_closed());
+    assert_eq!(pool.run_until(blocked), Err(SendError(2)));
+    assert_eq!(tx.try_send(3), Err(TrySendError::Closed(3)));
+
+    // senders are still alive, but the receiver has seen the last value
+    assert_eq!(pool.run_until(rx.recv()), Some(1));
+    assert_eq!(pool.run_until(rx.recv()), None);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn unbounded_close_drains_then_ends() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = unbounded_channel();
+    tx.send(1).unwrap();
+    rx.close();
+    assert_eq!(tx.send(2), Err(SendError(2)));
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(pool.run_until(rx.recv()), None);
+}
+
+#[test]
+fn dropping_the_receiver_fails_waiting_senders() {
+    let (tx, rx) = channel(1);
+    tx.try_send(1).unwrap();
+    let waker = futures::task::noop_waker();
+    let mut send = pin!(tx.send(2));
+    assert!(send.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    drop(rx);
+    assert_eq!(send.as_mut().poll(&mut Context::from_waker(&waker)), Poll::Ready(Err(SendError(2))));
+}
+
+#[test]
+fn channel_is_reusable_over_many_rounds() {
+    const ROUNDS: usize = 500;
+
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = channel(1);
+    pool.spawner().spawn_local(async move {
+        for i in 0..ROUNDS {
+            tx.send(i).await.unwrap();
+        }
+    }).unwrap();
+    let received = pool.run_until(async move {
+        let mut received = Vec::new();
+        while let Some(value) = rx.recv().await {
+            received.push(value);
+        }
+        received
+    });
+    assert_eq!(received, (0..ROUNDS).collect::<Vec<_>>());
+}
@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::Context;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::Notify;
+
+// the notify's `Debug` output lists one `Slot` per waiter slot it holds
+fn slots(notify: &Notify) -> usize {
+    format!("{:?}", notify).matches("Slot {").count()
+}
+
+fn spawn_waiter(pool: &LocalPool, notify: &Notify, order: &Rc<RefCell<Vec<usize>>>, id: usize) {
+    let (notify, order) = (notify.clone(), order.clone());
+    pool.spawner().spawn_local(async move {
+        notify.notified().await;
+        order.borrow_mut().push(id);
+    }).unwrap();
+}
+
+#[test]
+fn notify_one_wakes_the_oldest_waiter_or_stores_a_permit() {
+    let mut pool = LocalPool::new();
+    let notify = Notify::new();
+    let order = Rc::new(RefCell::new(Vec::new()));
+    for id in 0..3 {
+        spawn_waiter(&pool, &notify, &order, id);
+        pool.run_until_stalled();
+    }
+    notify.notify_one();
+    notify.notify_one();
+    pool.run_until_stalled();
+    assert_eq!(*order.borrow(), [0, 1]);
+
+    notify.notify_one();
+    // the permit outlasts the waiters, but only one of them
+    notify.notify_one();
+    notify.notify_one();
+    spawn_waiter(&pool, &notify, &order, 3);
+    spawn_waiter(&pool, &notify, &order, 4);
+    pool.run_until_stalled();
+    assert_eq!(*order.borrow(), [0, 1, 2, 3]);
+}
+
+#[test]
+fn notify_waiters_wakes_only_futures_created_before_it() {
+    let mut pool = LocalPool::new();
+    let notify = Notify::new();
+    let order = Rc::new(RefCell::new(Vec::new()));
+    // created but not yet polled still counts
+    let early = notify.notified();
+    for id in 0..3 {
+        spawn_waiter(&pool, &notify, &order, id);
+        pool.run_until_stalled();
+    }
+    notify.notify_waiters();
+    spawn_waiter(&pool, &notify, &order, 3);
+    pool.run_until_stalled();
+
+    assert_eq!(*order.borrow(), [0, 1, 2]);
+    pool.run_until(early);
+}
+
+#[test]
+fn notification_taken_by_a_cancelled_future_goes_to_the_next_waiter() {
+    let mut pool = LocalPool::new();
+    let notify = Notify::new();
+    let order = Rc::new(RefCell::new(Vec::new()));
+    let waker = futures::task::noop_waker();
+
+    let mut cancelled = Box::pin(notify.notified());
+    assert!(cancelled.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    spawn_waiter(&pool, &notify, &order, 1);
+    pool.run_until_stalled();
+
+    notify.notify_one();
+    drop(cancelled);
+    pool.run_until_stalled();
+    assert_eq!(*order.borrow(), [1]);
+
+    // a future cancelled before any notification leaves no permit
+    {
+        let mut cancelled = pin!(notify.notified());
+        assert!(cancelled.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    spawn_waiter(&pool, &notify, &order, 2);
+    pool.run_until_stalled();
+    assert_eq!(*order.borrow(), [1]);
+}
+
+#[test]
+fn reuse_over_many_rounds_keeps_its_slots() {
+    const ROUNDS: usize = 200;
+
+    let mut pool = LocalPool::new();
+    let (ping, pong) = (Notify::new(), Notify::new());
+    pool.spawner().spawn_local({
+        let (ping, pong) = (ping.clone(), pong.clone());
+        async move {
+            for _ in 0..ROUNDS {
+                ping.notified().await;
+                pong.notify_one();
+            }
+        }
+    }).unwrap();
+    pool.run_until(async {
+        for _ in 0..ROUNDS {
+            ping.notify_one();
+            pong.notified().await;
+        }
+    });
+
+    assert!(slots(&ping) <= 1 && slots(&pong) <= 1, "{} and {} slots after {} rounds", slots(&ping), slots(&pong), ROUNDS);
+}
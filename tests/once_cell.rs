@@ -0,0 +1,108 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::OnceCell;
+
+// Returns `Pending` once, waking itself.
+async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }).await
+}
+
+// the cell's `Debug` output lists one `Slot` per waiter slot it holds
+fn slots<T: std::fmt::Debug>(cell: &OnceCell<T>) -> usize {
+    format!("{:?}", cell).matches("Slot {").count()
+}
+
+#[test]
+fn concurrent_callers_share_one_initializer() {
+    let mut pool = LocalPool::new();
+    let cell = OnceCell::new();
+    let runs = Rc::new(RefCell::new(0));
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    for id in 0..5 {
+        let (cell, runs, seen) = (cell.clone(), runs.clone(), seen.clone());
+        pool.spawner().spawn_local(async move {
+            let value = *cell.get_or_init(|| async {
+                *runs.borrow_mut() += 1;
+                yield_now().await;
+                id
+            }).await;
+            seen.borrow_mut().push((id, value));
+        }).unwrap();
+    }
+    pool.run();
+
+    assert_eq!(*runs.borrow(), 1);
+    assert_eq!(*seen.borrow(), [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+}
+
+#[test]
+fn set_wakes_every_waiter() {
+    let mut pool = LocalPool::new();
+    let cell = OnceCell::new();
+    let woken = Rc::new(RefCell::new(0));
+    for _ in 0..3 {
+        let (cell, woken) = (cell.clone(), woken.clone());
+        pool.spawner().spawn_local(async move {
+            assert_eq!(*cell.wait().await, 7);
+            *woken.borrow_mut() += 1;
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+    assert_eq!(*woken.borrow(), 0);
+
+    assert_eq!(cell.set(7), Ok(()));
+    assert_eq!(cell.set(8), Err(8));
+    pool.run();
+    assert_eq!(*woken.borrow(), 3);
+    assert_eq!(slots(&cell), 3);
+}
+
+#[test]
+fn waiting_caller_takes_over_from_a_cancelled_initializer() {
+    let mut pool = LocalPool::new();
+    let cell = OnceCell::new();
+    let waker = futures::task::noop_waker();
+
+    let mut first = Box::pin(cell.get_or_init(std::future::pending::<u32>));
+    assert!(first.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    let second = pool.spawner().spawn_local_with_handle({
+        let cell = cell.clone();
+        async move { *cell.get_or_init(|| async { 2 }).await }
+    }).unwrap();
+    pool.run_until_stalled();
+    assert_eq!(cell.get(), None);
+
+    drop(first);
+    assert_eq!(pool.run_until(second), 2);
+    assert_eq!(cell.get(), Some(&2));
+}
+
+#[test]
+fn initializers_cancelled_over_many_rounds_leave_no_waiters() {
+    const ROUNDS: usize = 100;
+
+    let cell = OnceCell::new();
+    let waker = futures::task::noop_waker();
+    for _ in 0..ROUNDS {
+        let mut init = pin!(cell.get_or_init(std::future::pending::<u32>));
+        assert!(init.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+        let mut waiter = pin!(cell.wait());
+        assert!(waiter.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    assert_eq!(cell.get(), None);
+    assert!(slots(&cell) <= 1, "{} slots after {} rounds", slots(&cell), ROUNDS);
+    assert_eq!(LocalPool::new().run_until(cell.get_or_init(|| async { 5 })), &5);
+}
@@ -0,0 +1,83 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::oneshot::{self, TryRecvError};
+
+#[test]
+fn sending_wakes_the_waiting_receiver() {
+    let mut pool = LocalPool::new();
+    let (tx, rx) = oneshot::channel();
+    let received = pool.spawner().spawn_local_with_handle(rx).unwrap();
+    pool.run_until_stalled();
+    assert_eq!(tx.send(3), Ok(()));
+    assert_eq!(pool.run_until(received), Ok(3));
+}
+
+#[test]
+fn dropping_the_sender_fails_the_waiting_receiver() {
+    let (tx, mut rx) = oneshot::channel::<u32>();
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    let waker = futures::task::noop_waker();
+    assert!(Pin::new(&mut rx).poll(&mut Context::from_waker(&waker)).is_pending());
+    drop(tx);
+    assert!(matches!(Pin::new(&mut rx).poll(&mut Context::from_waker(&waker)), Poll::Ready(Err(_))));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Closed));
+}
+
+#[test]
+fn closing_or_dropping_the_receiver_hands_the_value_back() {
+    let (tx, mut rx) = oneshot::channel();
+    assert!(!tx.is_closed());
+    rx.close();
+    assert!(tx.is_closed());
+    assert_eq!(tx.send(1), Err(1));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Closed));
+
+    let (tx, rx) = oneshot::channel();
+    drop(rx);
+    assert!(tx.is_closed());
+    assert_eq!(tx.send(2), Err(2));
+}
+
+#[test]
+fn value_sent_before_close_is_still_received() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = oneshot::channel();
+    tx.send(4).unwrap();
+    rx.close();
+    assert_eq!(pool.run_until(rx), Ok(4));
+}
+
+#[test]
+fn receiver_polled_with_a_new_waker_is_the_one_woken() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = oneshot::channel();
+    // a first poll from a task that then goes away
+    let waker = futures::task::noop_waker();
+    assert!(Pin::new(&mut rx).poll(&mut Context::from_waker(&waker)).is_pending());
+    let received = pool.spawner().spawn_local_with_handle(rx).unwrap();
+    pool.run_until_stalled();
+    tx.send(5).unwrap();
+    assert_eq!(pool.run_until(received), Ok(5));
+}
+
+#[test]
+fn channels_can_be_chained_over_many_rounds() {
+    const ROUNDS: usize = 200;
+
+    let mut pool = LocalPool::new();
+    let (first_tx, mut rx) = oneshot::channel();
+    for _ in 0..ROUNDS {
+        let (tx, next) = oneshot::channel();
+        pool.spawner().spawn_local(async move {
+            let value: usize = rx.await.unwrap();
+            tx.send(value + 1).unwrap();
+        }).unwrap();
+        rx = next;
+    }
+    pool.run_until_stalled();
+    first_tx.send(0).unwrap();
+    assert_eq!(pool.run_until(rx), Ok(ROUNDS));
+}
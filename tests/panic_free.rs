@@ -0,0 +1,146 @@
+#![cfg(feature = "panic_free")]
+
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use futures::executor::block_on;
+use futures::task::noop_waker;
+use wasm_mutex::{lock_both, mpsc, CheckedLockFuture, LockError, Mutex};
+
+#[test]
+fn checked_lock_waits_like_lock() {
+    let mutex = Mutex::new(0);
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let held = mutex.try_lock().unwrap();
+    let mut lock = mutex.lock_checked();
+    assert!(Pin::new(&mut lock).poll(&mut cx).is_pending());
+    drop(held);
+
+    match Pin::new(&mut lock).poll(&mut cx) {
+        Poll::Ready(Ok(mut guard)) => *guard += 1,
+        _ => panic!("the released lock was not handed over"),
+    }
+    assert_eq!(*block_on(mutex.lock_checked()).unwrap(), 1);
+}
+
+#[test]
+fn polling_after_completion_is_an_error_not_a_panic() {
+    let mutex = Mutex::new(());
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut lock = mutex.lock_checked();
+    let Poll::Ready(Ok(guard)) = Pin::new(&mut lock).poll(&mut cx) else { panic!("lock was free") };
+    let repoll = catch_unwind(AssertUnwindSafe(|| Pin::new(&mut lock).poll(&mut cx)));
+    assert!(matches!(repoll, Ok(Poll::Ready(Err(LockError::Completed)))));
+
+    drop(guard);
+    assert!(!mutex.is_locked());
+}
+
+thread_local! {
+    static NESTED: RefCell<Option<CheckedLockFuture<'static, ()>>> = const { RefCell::new(None) };
+    static NESTED_ERROR: Cell<Option<LockError>> = const { Cell::new(None) };
+}
+
+// A waker that polls `NESTED` when it is cloned, which the mutex does while
+// its bookkeeping is borrowed.
+fn nesting_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        NESTED.with_borrow_mut(|nested| {
+            let Some(lock) = nested.as_mut() else { return };
+            let waker = noop_waker();
+            if let Poll::Ready(Err(error)) = Pin::new(lock).poll(&mut Context::from_waker(&waker)) {
+                NESTED_ERROR.set(Some(error));
+            }
+        });
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    // SAFETY: the vtable ignores the data pointer.
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
+
+#[test]
+fn a_checked_lock_polled_from_inside_the_mutex_is_inconsistent() {
+    let mutex: &'static Mutex<()> = Box::leak(Box::new(Mutex::new(())));
+    let held = mutex.try_lock().unwrap();
+    NESTED.set(Some(mutex.lock_checked()));
+
+    let waker = nesting_waker();
+    let mut lock = mutex.lock();
+    assert!(Pin::new(&mut lock).poll(&mut Context::from_waker(&waker)).is_pending());
+    assert_eq!(NESTED_ERROR.get(), Some(LockError::Inconsistent));
+
+    NESTED.set(None);
+    drop(lock);
+    drop(held);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn polling_a_lock_after_completion_stays_pending() {
+    let mutex = Mutex::new(());
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut lock = mutex.lock();
+    let Poll::Ready(guard) = Pin::new(&mut lock).poll(&mut cx) else { panic!("lock was free") };
+    assert!(Pin::new(&mut lock).poll(&mut cx).is_pending());
+    drop(guard);
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn locking_the_same_mutex_twice_never_resolves() {
+    let mutex = Mutex::new(());
+    let waker = noop_waker();
+    let mut both = pin!(lock_both(&mutex, &mutex));
+    assert!(both.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn a_guard_left_by_a_cancelled_unlocked_takes_the_free_lock_back() {
+    let mutex = Mutex::new(1);
+    let waker = noop_waker();
+    let mut guard = mutex.try_lock().unwrap();
+    {
+        let unlocked = pin!(guard.unlocked(std::future::pending::<()>()));
+        assert!(unlocked.poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    assert!(!mutex.is_locked());
+
+    *guard += 1;
+    assert!(mutex.is_locked());
+    drop(guard);
+    assert_eq!(*mutex.try_lock().unwrap(), 2);
+}
+
+#[test]
+fn zero_capacities_are_taken_as_one() {
+    let (tx, mut rx) = mpsc::channel(0);
+    tx.try_send(1).unwrap();
+    assert!(tx.try_send(2).is_err());
+    assert_eq!(rx.try_recv().unwrap(), 1);
+
+    #[cfg(feature = "std")]
+    assert_eq!(wasm_mutex::ShardedMutex::<u32>::new(0).shards().len(), 1);
+}
+
+#[cfg(feature = "sink")]
+#[test]
+fn start_send_without_poll_ready_takes_a_free_lock() {
+    use futures::Sink;
+    use wasm_mutex::GuardedSink;
+
+    let mutex = Mutex::new(Vec::new());
+    let mut sink = GuardedSink::new(mutex.clone());
+    Pin::new(&mut sink).start_send(1).unwrap();
+    assert_eq!(*mutex.try_lock().unwrap(), [1]);
+}
@@ -0,0 +1,43 @@
+#![cfg(all(feature = "web-profiling", target_arch = "wasm32", target_os = "unknown"))]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use futures::task::noop_waker;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen_test::wasm_bindgen_test;
+use wasm_mutex::Mutex;
+
+#[wasm_bindgen]
+extern "C" {
+    type PerformanceEntry;
+
+    #[wasm_bindgen(method, getter)]
+    fn name(this: &PerformanceEntry) -> String;
+
+    #[wasm_bindgen(js_namespace = performance, js_name = getEntriesByType)]
+    fn get_entries_by_type(kind: &str) -> Vec<PerformanceEntry>;
+}
+
+fn measures() -> Vec<String> {
+    get_entries_by_type("measure").iter().map(PerformanceEntry::name).collect()
+}
+
+#[wasm_bindgen_test]
+fn a_contended_lock_is_measured() {
+    let mutex = Mutex::builder().name("profiled").build(());
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let held = mutex.try_lock().unwrap();
+    let mut lock = mutex.lock();
+    assert!(Pin::new(&mut lock).poll(&mut cx).is_pending());
+    drop(held);
+    let guard = Pin::new(&mut lock).poll(&mut cx);
+    assert!(guard.is_ready());
+    drop(guard);
+
+    let measures = measures();
+    assert!(measures.iter().any(|name| name == "profiled lock wait"), "{:?}", measures);
+    assert!(measures.iter().any(|name| name == "profiled lock held"), "{:?}", measures);
+}
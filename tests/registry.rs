@@ -0,0 +1,60 @@
+#![cfg(feature = "registry")]
+
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{lock_report, snapshot, Mutex};
+
+#[test]
+fn report_lists_live_locks_with_their_holder_and_waiters() {
+    let mut pool = LocalPool::new();
+    let free = Mutex::new(());
+    let busy = Mutex::new(());
+
+    let (held, line) = (busy.try_lock().unwrap(), line!());
+    let waiter = busy.clone();
+    pool.spawner().spawn_local(async move {
+        drop(waiter.lock().await);
+    }).unwrap();
+    pool.run_until_stalled();
+
+    let report = lock_report();
+    let lines: Vec<_> = report.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", report);
+    assert!(lines[0].ends_with(": free, 0 waiting"), "{}", report);
+    assert!(lines[1].contains(&format!("held since {}:{}:", file!(), line)), "{}", report);
+    assert!(lines[1].ends_with(", 1 waiting"), "{}", report);
+
+    drop(held);
+    pool.run();
+    drop(free);
+    assert!(lock_report().ends_with(": free, 0 waiting"));
+    assert_eq!(lock_report().lines().count(), 1);
+}
+
+#[test]
+fn report_uses_the_mutex_name() {
+    let _mutex = Mutex::builder().name("session_state").build(());
+    assert_eq!(lock_report(), "session_state: free, 0 waiting");
+}
+
+#[test]
+fn snapshot_describes_each_live_lock() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::builder().name("session_state").build(());
+    let (held, line) = (mutex.try_lock().unwrap(), line!());
+    let waiter = mutex.clone();
+    pool.spawner().spawn_local(async move {
+        drop(waiter.lock().await);
+    }).unwrap();
+    pool.run_until_stalled();
+
+    let locks = snapshot();
+    assert_eq!(locks.len(), 1);
+    assert_eq!((locks[0].name, locks[0].held, locks[0].waiters), (Some("session_state"), true, 1));
+    let holder = locks[0].holder.unwrap();
+    assert_eq!((holder.file(), holder.line()), (file!(), line));
+
+    drop(held);
+    pool.run();
+    assert_eq!((snapshot()[0].held, snapshot()[0].holder, snapshot()[0].waiters), (false, None, 0));
+}
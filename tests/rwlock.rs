@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::RwLock;
+
+// Returns `Pending` once, waking itself.
+async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }).await
+}
+
+fn spawn_access(pool: &LocalPool, lock: &RwLock<()>, order: &Rc<RefCell<Vec<String>>>, name: &str, write: bool) {
+    let (lock, order, name) = (lock.clone(), order.clone(), name.to_string());
+    pool.spawner().spawn_local(async move {
+        if write {
+            let _guard = lock.write().await;
+            order.borrow_mut().push(name);
+        } else {
+            let _guard = lock.read().await;
+            order.borrow_mut().push(name);
+        }
+    }).unwrap();
+}
+
+#[test]
+fn debug_shows_the_value_unless_write_locked() {
+    let lock = RwLock::new(1);
+    assert_eq!(format!("{:?}", lock), "RwLock { value: 1 }");
+    let read = lock.try_read().unwrap();
+    assert_eq!(format!("{:?} {:?}", lock, read), "RwLock { value: 1 } 1");
+    drop(read);
+
+    let mut write = lock.try_write().unwrap();
+    *write = 2;
+    assert_eq!(format!("{:?} {:?}", lock, write), "RwLock { value: <locked, 0 waiters> } 2");
+    drop(write);
+    // formatting took no guard, so nothing is held afterwards
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn unsized_values_can_be_shared() {
+    let lock: RwLock<[u8]> = RwLock::from_box(vec![1, 2, 3].into_boxed_slice());
+    assert_eq!(lock.try_read().unwrap().len(), 3);
+    lock.try_write().unwrap()[0] = 9;
+    assert_eq!(format!("{:?}", lock.try_read().unwrap()), "[9, 2, 3]");
+}
+
+#[test]
+fn queued_writer_holds_back_readers_behind_it() {
+    let mut pool = LocalPool::new();
+    let lock = RwLock::new(());
+    let order = Rc::new(RefCell::new(Vec::new()));
+
+    let held = lock.try_write().unwrap();
+    for (name, write) in [("r1", false), ("r2", false), ("w1", true), ("r3", false), ("w2", true)] {
+        spawn_access(&pool, &lock, &order, name, write);
+        pool.run_until_stalled();
+    }
+    // newcomers do not get ahead of the queue
+    assert!(lock.try_read().is_none());
+    drop(held);
+    pool.run();
+
+    assert_eq!(*order.borrow(), ["r1", "r2", "w1", "r3", "w2"]);
+}
+
+#[test]
+fn readers_share_the_lock() {
+    let lock = RwLock::new(5);
+    let first = lock.try_read().unwrap();
+    let second = lock.try_read().unwrap();
+    assert_eq!(*first + *second, 10);
+    assert!(lock.try_write().is_none());
+    drop(first);
+    assert!(lock.try_write().is_none());
+    drop(second);
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn cancelled_writer_lets_the_readers_behind_it_in() {
+    let mut pool = LocalPool::new();
+    let lock = RwLock::new(());
+    let order = Rc::new(RefCell::new(Vec::new()));
+    let waker = futures::task::noop_waker();
+
+    let reading = lock.try_read().unwrap();
+    let mut writer = Box::pin(lock.write());
+    assert!(writer.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    spawn_access(&pool, &lock, &order, "r", false);
+    pool.run_until_stalled();
+    assert!(order.borrow().is_empty());
+
+    drop(writer);
+    pool.run_until_stalled();
+    assert_eq!(*order.borrow(), ["r"]);
+    drop(reading);
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn writer_cancelled_after_being_granted_releases_the_lock() {
+    let lock = RwLock::new(());
+    let waker = futures::task::noop_waker();
+    let held = lock.try_write().unwrap();
+    {
+        let mut writer = pin!(lock.write());
+        assert!(writer.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+        drop(held);
+    }
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn reuse_over_many_rounds() {
+    const TASKS: usize = 10;
+    const ROUNDS: usize = 100;
+
+    let mut pool = LocalPool::new();
+    let lock = RwLock::new(0);
+    for i in 0..TASKS {
+        let lock = lock.clone();
+        pool.spawner().spawn_local(async move {
+            for _ in 0..ROUNDS {
+                if i % 3 == 0 {
+                    *lock.write().await += 1;
+                } else {
+                    let _guard = lock.read().await;
+                    yield_now().await;
+                }
+            }
+        }).unwrap();
+    }
+    pool.run();
+
+    assert_eq!(*lock.try_read().unwrap(), 4 * ROUNDS);
+    assert!(lock.try_write().is_some());
+}
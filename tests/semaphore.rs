@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::Semaphore;
+
+// Returns `Pending` once, waking itself.
+async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }).await
+}
+
+// the semaphore's `Debug` output lists one `Slot` per waiter slot it holds
+fn slots(semaphore: &Semaphore) -> usize {
+    format!("{:?}", semaphore).matches("Slot {").count()
+}
+
+#[test]
+fn waiters_are_given_permits_in_arrival_order() {
+    let mut pool = LocalPool::new();
+    let semaphore = Semaphore::new(0);
+    let order = Rc::new(RefCell::new(Vec::new()));
+
+    for i in 0..5 {
+        let (semaphore, order) = (semaphore.clone(), order.clone());
+        pool.spawner().spawn_local(async move {
+            semaphore.acquire().await.forget();
+            order.borrow_mut().push(i);
+        }).unwrap();
+        pool.run_until_stalled();
+    }
+    // queued waiters come first, so a newcomer cannot take a permit
+    semaphore.add_permits(2);
+    assert!(semaphore.try_acquire().is_none());
+    pool.run_until_stalled();
+    assert_eq!(*order.borrow(), [0, 1]);
+
+    semaphore.add_permits(3);
+    pool.run();
+    assert_eq!(*order.borrow(), [0, 1, 2, 3, 4]);
+    assert_eq!(semaphore.available_permits(), 0);
+}
+
+#[test]
+fn dropped_permits_are_returned() {
+    let semaphore = Semaphore::new(2);
+    let first = semaphore.try_acquire().unwrap();
+    let _second = semaphore.try_acquire().unwrap();
+    assert!(semaphore.try_acquire().is_none());
+    drop(first);
+    assert_eq!(semaphore.available_permits(), 1);
+    assert!(semaphore.try_acquire().is_some());
+}
+
+#[test]
+fn cancelled_waiter_passes_on_a_permit_it_was_given() {
+    let mut pool = LocalPool::new();
+    let semaphore = Semaphore::new(0);
+    let waker = futures::task::noop_waker();
+    let mut cancelled = Box::pin(semaphore.acquire());
+    assert!(cancelled.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    let next = pool.spawner().spawn_local_with_handle({
+        let semaphore = semaphore.clone();
+        async move { semaphore.acquire().await.forget() }
+    }).unwrap();
+    pool.run_until_stalled();
+
+    semaphore.add_permits(1);
+    drop(cancelled);
+    pool.run_until(next);
+    assert_eq!(semaphore.available_permits(), 0);
+
+    // a waiter cancelled before it was given anything makes up no permit
+    {
+        let mut cancelled = pin!(semaphore.acquire());
+        assert!(cancelled.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    assert_eq!(semaphore.available_permits(), 0);
+    assert_eq!(slots(&semaphore), 2);
+}
+
+#[test]
+fn reuse_over_many_rounds_keeps_its_slots() {
+    const TASKS: usize = 10;
+    const ROUNDS: usize = 100;
+
+    let mut pool = LocalPool::new();
+    let semaphore = Semaphore::new(3);
+    let inside = Rc::new(RefCell::new((0, 0)));
+    for _ in 0..TASKS {
+        let (semaphore, inside) = (semaphore.clone(), inside.clone());
+        pool.spawner().spawn_local(async move {
+            for _ in 0..ROUNDS {
+                let _permit = semaphore.acquire().await;
+                {
+                    let (now, most) = &mut *inside.borrow_mut();
+                    *now += 1;
+                    *most = (*most).max(*now);
+                }
+                yield_now().await;
+                inside.borrow_mut().0 -= 1;
+            }
+        }).unwrap();
+    }
+    pool.run();
+
+    assert_eq!(inside.borrow().1, 3);
+    assert_eq!(semaphore.available_permits(), 3);
+    assert!(slots(&semaphore) <= TASKS, "{} slots after {} rounds", slots(&semaphore), ROUNDS);
+}
@@ -0,0 +1,15 @@
+#![cfg(feature = "serde")]
+
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::de::value::Error;
+use wasm_mutex::{Mutex, RwLock};
+
+#[test]
+fn locks_deserialize_from_their_value() {
+    let mutex: Mutex<u32> = Mutex::deserialize(IntoDeserializer::<Error>::into_deserializer(7u32)).unwrap();
+    assert_eq!(*mutex.try_lock().unwrap(), 7);
+
+    let rwlock: RwLock<u32> = RwLock::deserialize(IntoDeserializer::<Error>::into_deserializer(8u32)).unwrap();
+    assert_eq!(*rwlock.try_read().unwrap(), 8);
+}
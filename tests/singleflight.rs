@@ -0,0 +1,69 @@
+#![cfg(feature = "std")]
+
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::Context;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{Notify, Singleflight};
+
+#[test]
+fn concurrent_calls_share_one_run() {
+    let mut pool = LocalPool::new();
+    let flights = Singleflight::new();
+    let runs = Rc::new(Cell::new(0));
+    let release = Rc::new(Notify::new());
+    let handles: Vec<_> = (0..3).map(|_| {
+        let (flights, runs, release) = (flights.clone(), runs.clone(), release.clone());
+        pool.spawner().spawn_local_with_handle(async move {
+            flights.run("user", || async {
+                runs.set(runs.get() + 1);
+                release.notified().await;
+                7
+            }).await
+        }).unwrap()
+    }).collect();
+    pool.run_until_stalled();
+    assert!(flights.is_running(&"user"));
+
+    release.notify_waiters();
+    for handle in handles {
+        assert_eq!(pool.run_until(handle), 7);
+    }
+    assert_eq!(runs.get(), 1);
+    assert!(!flights.is_running(&"user"));
+}
+
+#[test]
+fn cancelling_the_only_call_clears_the_flight() {
+    let flights = Singleflight::<&str, u32>::new();
+    let waker = futures::task::noop_waker();
+    {
+        let mut call = pin!(flights.run("user", std::future::pending::<u32>));
+        assert!(call.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+        assert!(flights.is_running(&"user"));
+    }
+    assert!(!flights.is_running(&"user"));
+}
+
+#[test]
+fn waiting_call_takes_over_from_a_cancelled_one() {
+    let mut pool = LocalPool::new();
+    let flights = Singleflight::new();
+    let waker = futures::task::noop_waker();
+
+    let mut first = Box::pin(flights.run("user", std::future::pending::<u32>));
+    assert!(first.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    let second = pool.spawner().spawn_local_with_handle({
+        let flights = flights.clone();
+        async move { flights.run("user", || async { 8 }).await }
+    }).unwrap();
+    pool.run_until_stalled();
+
+    drop(first);
+    assert!(flights.is_running(&"user"));
+    assert_eq!(pool.run_until(second), 8);
+    assert!(!flights.is_running(&"user"));
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "sink")]
+
+use std::pin::Pin;
+use std::task::Context;
+use futures::executor::LocalPool;
+use futures::task::{noop_waker, LocalSpawnExt};
+use futures::{Sink, SinkExt};
+use wasm_mutex::{GuardedSink, Mutex};
+
+#[test]
+fn producers_share_one_sink() {
+    let mut pool = LocalPool::new();
+    let sink = Mutex::new(Vec::new());
+
+    for producer in 0..3 {
+        let mut sink = GuardedSink::new(sink.clone());
+        pool.spawner().spawn_local(async move {
+            for i in 0..4 {
+                sink.send(producer * 10 + i).await.unwrap();
+            }
+        }).unwrap();
+    }
+    pool.run();
+
+    let mut received = sink.try_lock().unwrap().clone();
+    received.sort();
+    assert_eq!(received, [0, 1, 2, 3, 10, 11, 12, 13, 20, 21, 22, 23]);
+}
+
+#[test]
+fn lock_is_held_from_poll_ready_to_start_send() {
+    let sink = Mutex::new(Vec::new());
+    let mut a = GuardedSink::new(sink.clone());
+    let mut b = GuardedSink::new(sink.clone());
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(Pin::new(&mut a).poll_ready(&mut cx).is_ready());
+    assert!(sink.is_locked());
+    assert!(Pin::new(&mut b).poll_ready(&mut cx).is_pending());
+
+    Pin::new(&mut a).start_send(1).unwrap();
+    assert!(Pin::new(&mut b).poll_ready(&mut cx).is_ready());
+    Pin::new(&mut b).start_send(2).unwrap();
+    assert!(!sink.is_locked());
+    assert_eq!(*sink.try_lock().unwrap(), [1, 2]);
+}
+
+#[test]
+fn producers_share_one_bounded_channel() {
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+
+    let mut pool = LocalPool::new();
+    let (tx, rx) = mpsc::channel(0);
+    let sink = Mutex::new(tx);
+
+    for producer in 0..3 {
+        let mut sink = GuardedSink::new(sink.clone());
+        pool.spawner().spawn_local(async move {
+            for i in 0..4 {
+                sink.send(producer * 10 + i).await.unwrap();
+            }
+        }).unwrap();
+    }
+
+    let mut received = pool.run_until(rx.take(12).collect::<Vec<_>>());
+    received.sort();
+    assert_eq!(received, [0, 1, 2, 3, 10, 11, 12, 13, 20, 21, 22, 23]);
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "stats")]
+
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::Mutex;
+
+#[test]
+fn stats_count_contended_acquisitions() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(());
+
+    let held = mutex.try_lock().unwrap();
+    for _ in 0..3 {
+        let mutex = mutex.clone();
+        pool.spawner().spawn_local(async move {
+            drop(mutex.lock().await);
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+
+    let stats = mutex.stats();
+    assert_eq!((stats.acquisitions, stats.contended, stats.waiters, stats.max_waiters), (1, 0, 3, 3));
+
+    drop(held);
+    pool.run();
+    drop(mutex.try_lock().unwrap());
+
+    let stats = mutex.stats();
+    assert_eq!((stats.acquisitions, stats.contended, stats.waiters, stats.max_waiters), (5, 3, 0, 3));
+    assert_eq!(mutex.clone().stats(), stats);
+}
+
+#[test]
+fn formatting_is_not_an_acquisition() {
+    let mutex = Mutex::new(1);
+    assert_eq!(format!("{:?}", mutex), "Mutex { value: 1 }");
+    let held = mutex.try_lock().unwrap();
+    assert_eq!(format!("{:?}", mutex), "Mutex { value: <locked, 0 waiters> }");
+    drop(held);
+    assert_eq!(mutex.stats().acquisitions, 1);
+}
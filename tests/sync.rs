@@ -0,0 +1,139 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use futures::executor::block_on;
+use wasm_mutex::sync::{Mutex, Spin};
+
+fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+// plain wasm32-wasip1 has no threads to spawn
+#[test]
+#[cfg_attr(all(target_os = "wasi", not(target_feature = "atomics")), ignore)]
+fn threads_share_one_mutex() {
+    const THREADS: usize = 8;
+    const ROUNDS: usize = 1000;
+
+    let mutex = Mutex::new(0);
+    let handles: Vec<_> = (0..THREADS).map(|_| {
+        let mutex = mutex.clone();
+        thread::spawn(move || block_on(async {
+            for _ in 0..ROUNDS {
+                *mutex.lock().await += 1;
+            }
+        }))
+    }).collect();
+    handles.into_iter().for_each(|handle| handle.join().unwrap());
+
+    assert_eq!(*mutex.try_lock().unwrap(), THREADS * ROUNDS);
+    assert_eq!(mutex.waiter_count(), 0);
+}
+
+#[test]
+#[cfg_attr(all(target_os = "wasi", not(target_feature = "atomics")), ignore)]
+fn futures_and_guards_are_send() {
+    let mutex = Mutex::new(Vec::<u8>::new());
+    assert_send_sync(&mutex);
+    assert_send_sync(&mutex.lock());
+    assert_send_sync(&mutex.lock_owned());
+
+    let guard = mutex.try_lock_owned().unwrap();
+    thread::spawn(move || drop(guard)).join().unwrap();
+    assert!(!mutex.is_locked());
+}
+
+#[test]
+fn local_and_sync_live_side_by_side() {
+    let local = wasm_mutex::local::Mutex::new(1);
+    let shared = Mutex::new(2);
+    block_on(async {
+        *local.lock().await += *shared.lock().await;
+    });
+    assert_eq!(local.into_inner().unwrap(), 3);
+}
+
+#[test]
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+#[cfg_attr(all(target_os = "wasi", not(target_feature = "atomics")), ignore)]
+fn blocking_lock_waits_for_another_thread() {
+    let mutex = Mutex::new(0);
+    let held = mutex.try_lock().unwrap();
+    let waiter = thread::spawn({
+        let mutex = mutex.clone();
+        move || *mutex.blocking_lock() += 1
+    });
+    while mutex.waiter_count() == 0 {
+        thread::yield_now();
+    }
+    drop(held);
+    waiter.join().unwrap();
+    assert_eq!(*mutex.blocking_lock(), 1);
+}
+
+#[test]
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+fn blocking_try_lock_for_gives_up_its_place_on_timeout() {
+    let mutex = Mutex::new(());
+    let held = mutex.try_lock().unwrap();
+    assert!(mutex.blocking_try_lock_for(std::time::Duration::from_millis(10)).is_none());
+    assert_eq!(mutex.waiter_count(), 0);
+    drop(held);
+    assert!(mutex.blocking_try_lock_for(std::time::Duration::from_millis(10)).is_some());
+}
+
+#[test]
+fn spinning_lock_queues_once_its_tries_run_out() {
+    let mutex = Mutex::with_spin((), Spin { attempts: 4, initial_backoff: 1, max_backoff: 8 });
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let held = mutex.try_lock().unwrap();
+    let mut lock = mutex.lock();
+    assert!(Pin::new(&mut lock).poll(&mut cx).is_pending());
+    assert_eq!(mutex.waiter_count(), 1);
+    drop(held);
+    assert!(Pin::new(&mut lock).poll(&mut cx).is_ready());
+}
+
+#[test]
+#[cfg_attr(all(target_os = "wasi", not(target_feature = "atomics")), ignore)]
+fn spinning_threads_share_one_mutex() {
+    const THREADS: usize = 4;
+    const ROUNDS: usize = 1000;
+
+    let mutex = Mutex::with_spin(0, Spin { attempts: 16, initial_backoff: 1, max_backoff: 64 });
+    let handles: Vec<_> = (0..THREADS).map(|_| {
+        let mutex = mutex.clone();
+        thread::spawn(move || block_on(async {
+            for _ in 0..ROUNDS {
+                *mutex.lock().await += 1;
+            }
+        }))
+    }).collect();
+    handles.into_iter().for_each(|handle| handle.join().unwrap());
+
+    assert_eq!(*mutex.try_lock().unwrap(), THREADS * ROUNDS);
+    assert_eq!(mutex.waiter_count(), 0);
+}
+
+#[test]
+fn released_lock_is_handed_to_the_queued_waiter() {
+    let mutex = Mutex::new(());
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let held = mutex.try_lock().unwrap();
+    let mut lock = mutex.lock();
+    assert!(Pin::new(&mut lock).poll(&mut cx).is_pending());
+    drop(held);
+    // a newcomer cannot barge in while the lock is on its way to the waiter
+    assert!(mutex.is_locked());
+    assert!(mutex.try_lock().is_none());
+    let guard = match Pin::new(&mut lock).poll(&mut cx) {
+        Poll::Ready(guard) => guard,
+        Poll::Pending => panic!("the released lock was not handed over"),
+    };
+    drop(guard);
+    assert!(!mutex.is_locked());
+    assert!(mutex.try_lock().is_some());
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "std")]
+
+use futures::executor::{block_on, LocalPool};
+use futures::task::LocalSpawnExt;
+use std::future::poll_fn;
+use std::task::Poll;
+use wasm_mutex::{current_task, task_scope, Mutex};
+
+async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }).await
+}
+
+#[test]
+fn scopes_give_each_task_its_own_id() {
+    let first = task_scope(async { current_task() });
+    let second = task_scope(async { current_task() });
+    let (first_id, second_id) = (first.id(), second.id());
+
+    assert_ne!(first_id, second_id);
+    assert_eq!(block_on(first), Some(first_id));
+    assert_eq!(block_on(second), Some(second_id));
+    assert_eq!(current_task(), None);
+}
+
+#[test]
+fn other_tasks_still_wait_for_the_lock() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::new(Vec::new());
+
+    for i in 0..3 {
+        let mutex = mutex.clone();
+        pool.spawner().spawn_local(task_scope(async move {
+            let mut guard = mutex.lock().await;
+            yield_now().await;
+            guard.push(i);
+        })).unwrap();
+    }
+    pool.run();
+
+    assert_eq!(*mutex.try_lock().unwrap(), [0, 1, 2]);
+}
+
+#[test]
+#[cfg(all(debug_assertions, not(feature = "panic_free")))]
+#[should_panic(expected = "is waiting to lock session_state, which it already holds")]
+fn relocking_from_the_same_task_panics() {
+    let mutex = Mutex::builder().name("session_state").build(());
+    block_on(task_scope(async {
+        let _guard = mutex.lock().await;
+        let _again = mutex.lock().await;
+    }));
+}
+
+#[test]
+fn leveled_locks_taken_in_increasing_order_are_fine() {
+    let config = Mutex::builder().name("config").level(1).build(());
+    let cache = Mutex::builder().name("cache").level(2).build(());
+    block_on(task_scope(async {
+        for _ in 0..2 {
+            let _config = config.lock().await;
+            let _cache = cache.lock().await;
+        }
+    }));
+}
+
+#[test]
+#[cfg(all(debug_assertions, not(feature = "panic_free")))]
+#[should_panic(expected = "is locking config at level 1 while holding cache at level 2")]
+fn leveled_locks_taken_out_of_order_panic() {
+    let config = Mutex::builder().name("config").level(1).build(());
+    let cache = Mutex::builder().name("cache").level(2).build(());
+    block_on(task_scope(async {
+        let _cache = cache.lock().await;
+        let _config = config.lock().await;
+    }));
+}
+
+#[test]
+#[cfg(all(debug_assertions, not(feature = "panic_free")))]
+#[should_panic(expected = "is locking config at level 1 while holding cache at level 2")]
+fn leveled_try_lock_taken_out_of_order_panics() {
+    let config = Mutex::builder().name("config").level(1).build(());
+    let cache = Mutex::builder().name("cache").level(2).build(());
+    block_on(task_scope(async {
+        let _cache = cache.try_lock().unwrap();
+        let _config = config.try_lock();
+    }));
+}
+
+#[test]
+#[cfg(all(debug_assertions, feature = "panic_free"))]
+fn leveled_locks_taken_out_of_order_are_only_reported_with_panic_free() {
+    let config = Mutex::builder().name("config").level(1).build(());
+    let cache = Mutex::builder().name("cache").level(2).build(());
+    block_on(task_scope(async {
+        let _cache = cache.lock().await;
+        let _config = config.lock().await;
+    }));
+}
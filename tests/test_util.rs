@@ -0,0 +1,51 @@
+#![cfg(feature = "test_util")]
+
+use wasm_mutex::Mutex;
+use wasm_mutex::test_util::{AcquisitionLog, Executor};
+
+#[test]
+fn contenders_acquire_in_spawn_order() {
+    let mut executor = Executor::new();
+    let mutex = Mutex::new(());
+    let log = AcquisitionLog::new();
+
+    let held = mutex.try_lock().unwrap();
+    log.spawn_contenders(&mut executor, &mutex, 5, 2);
+    assert_eq!(executor.pending(), 5);
+    assert!(log.order().is_empty());
+
+    drop(held);
+    executor.run_until_stalled();
+    assert_eq!(executor.pending(), 0);
+    log.assert_fifo();
+}
+
+#[test]
+fn runs_interleave_the_same_way_every_time() {
+    let run = || {
+        let mut executor = Executor::new();
+        let mutex = Mutex::new(());
+        let log = AcquisitionLog::new();
+        for id in 0..4 {
+            executor.spawn(log.contender(&mutex, id, id));
+            executor.spawn(log.contender(&mutex, id + 4, 1));
+        }
+        executor.run_until_stalled();
+        log.order()
+    };
+
+    let first = run();
+    assert_eq!(first.len(), 8);
+    for _ in 0..10 {
+        assert_eq!(run(), first);
+    }
+}
+
+#[test]
+#[should_panic(expected = "not acquired first come, first served")]
+fn assert_fifo_reports_out_of_order_acquisitions() {
+    let log = AcquisitionLog::new();
+    log.record(1);
+    log.record(0);
+    log.assert_fifo();
+}
@@ -0,0 +1,32 @@
+#![cfg(all(feature = "timers", target_arch = "wasm32", target_os = "unknown"))]
+
+use std::time::Duration;
+use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen_test::wasm_bindgen_test;
+use wasm_mutex::Mutex;
+
+#[wasm_bindgen_test]
+async fn lock_timeout_gives_up_on_a_lock_held_past_the_deadline() {
+    let mutex = Mutex::new(());
+    let _held = mutex.try_lock().unwrap();
+    assert!(mutex.lock_timeout(Duration::from_millis(10)).await.is_err());
+    assert_eq!(mutex.waiter_count(), 0);
+}
+
+#[wasm_bindgen_test]
+async fn lock_timeout_takes_a_lock_released_before_the_deadline() {
+    let mutex = Mutex::new(0);
+    let held = mutex.try_lock().unwrap();
+    let (locked, ()) = futures::join!(mutex.lock_timeout(Duration::from_secs(5)), async move {
+        TimeoutFuture::new(10).await;
+        drop(held);
+    });
+    *locked.unwrap() += 1;
+    assert_eq!(*mutex.try_lock().unwrap(), 1);
+}
+
+#[wasm_bindgen_test]
+async fn lock_until_a_past_deadline_still_takes_a_free_lock() {
+    let mutex = Mutex::new(());
+    assert!(mutex.lock_until(0.0).await.is_ok());
+}
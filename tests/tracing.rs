@@ -0,0 +1,81 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt;
+use std::sync::{Arc, Mutex as StdMutex};
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use wasm_mutex::Mutex;
+
+#[derive(Clone, Default)]
+struct Recorder {
+    log: Arc<StdMutex<Vec<String>>>,
+    next_id: Arc<StdMutex<u64>>,
+}
+
+struct Message<'a>(&'a mut String);
+
+impl Visit for Message<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            *self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.log.lock().unwrap().push(format!("open {}", span.metadata().name()));
+        let mut next_id = self.next_id.lock().unwrap();
+        *next_id += 1;
+        Id::from_u64(*next_id)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = String::new();
+        event.record(&mut Message(&mut message));
+        self.log.lock().unwrap().push(message);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn contended_lock_traces_its_wait_hold_and_release() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut pool = LocalPool::new();
+        let mutex = Mutex::new(());
+
+        let held = mutex.try_lock().unwrap();
+        let waiter = mutex.clone();
+        pool.spawner().spawn_local(async move {
+            drop(waiter.lock().await);
+        }).unwrap();
+        pool.run_until_stalled();
+        drop(held);
+        pool.run();
+    });
+
+    assert_eq!(*recorder.log.lock().unwrap(), [
+        "lock acquired",
+        "open lock held",
+        "open lock wait",
+        "lock released",
+        "lock acquired",
+        "open lock held",
+        "lock released",
+    ]);
+}
@@ -0,0 +1,28 @@
+use futures::executor::block_on;
+use wasm_mutex::{AsyncLock, AsyncRwLock, Mutex, RwLock};
+
+async fn bump<L: AsyncLock<Target = u32>>(lock: &L) -> u32 {
+    let mut guard = lock.lock().await;
+    *guard += 1;
+    *guard
+}
+
+async fn bump_and_read<L: AsyncRwLock<Target = u32>>(lock: &L) -> u32 {
+    *lock.write().await += 1;
+    *lock.read().await
+}
+
+#[test]
+fn generic_code_drives_the_crate_locks() {
+    let mutex = Mutex::new(1);
+    let rwlock = RwLock::new(1);
+    block_on(async {
+        assert_eq!(bump(&mutex).await, 2);
+        assert_eq!(bump_and_read(&rwlock).await, 2);
+    });
+
+    let _held = AsyncLock::try_lock(&mutex).unwrap();
+    assert!(AsyncLock::try_lock(&mutex).is_none());
+    let _reader = AsyncRwLock::try_read(&rwlock).unwrap();
+    assert!(AsyncRwLock::try_write(&rwlock).is_none());
+}
@@ -0,0 +1,102 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::Context;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::watch;
+
+// the sender's `Debug` output lists one `Slot` per waiter slot it holds
+fn slots<T: std::fmt::Debug>(tx: &watch::Sender<T>) -> usize {
+    format!("{:?}", tx).matches("Slot {").count()
+}
+
+#[test]
+fn every_receiver_is_woken_by_a_change() {
+    let mut pool = LocalPool::new();
+    let (tx, rx) = watch::channel(0);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    for id in 0..3 {
+        let (mut rx, seen) = (rx.clone(), seen.clone());
+        pool.spawner().spawn_local(async move {
+            rx.changed().await.unwrap();
+            let value = *rx.borrow();
+            seen.borrow_mut().push((id, value));
+        }).unwrap();
+        pool.run_until_stalled();
+    }
+    tx.send(5);
+    pool.run();
+    assert_eq!(*seen.borrow(), [(0, 5), (1, 5), (2, 5)]);
+}
+
+#[test]
+fn changes_between_polls_are_seen_once() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = watch::channel(0);
+    tx.send(1);
+    tx.send_modify(|value| *value += 1);
+    assert_eq!(rx.has_changed(), Ok(true));
+    pool.run_until(rx.changed()).unwrap();
+    assert_eq!(*rx.borrow(), 2);
+    assert_eq!(rx.has_changed(), Ok(false));
+
+    // a new subscriber starts out up to date
+    let late = tx.subscribe();
+    assert_eq!(late.has_changed(), Ok(false));
+}
+
+#[test]
+fn dropping_the_sender_ends_waits_after_the_last_change() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = watch::channel(0);
+    let waiting = pool.spawner().spawn_local_with_handle({
+        let mut rx = rx.clone();
+        async move { rx.changed().await }
+    }).unwrap();
+    pool.run_until_stalled();
+
+    tx.send(1);
+    drop(tx);
+    assert_eq!(pool.run_until(waiting), Ok(()));
+    assert_eq!(pool.run_until(rx.changed()), Ok(()));
+    assert!(pool.run_until(rx.changed()).is_err());
+    assert!(rx.has_changed().is_err());
+}
+
+#[test]
+fn cancelled_waits_leave_their_slots_free() {
+    let (tx, mut rx) = watch::channel(0);
+    let waker = futures::task::noop_waker();
+    for _ in 0..100 {
+        let mut changed = pin!(rx.changed());
+        assert!(changed.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+    }
+    assert_eq!(slots(&tx), 1);
+    tx.send(1);
+    assert_eq!(rx.has_changed(), Ok(true));
+}
+
+#[test]
+fn receiver_follows_the_sender_over_many_rounds() {
+    const ROUNDS: usize = 200;
+
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = watch::channel(0);
+    let (ack_tx, mut ack_rx) = watch::channel(0);
+    pool.spawner().spawn_local(async move {
+        while rx.changed().await.is_ok() {
+            let value = *rx.borrow();
+            ack_tx.send(value);
+        }
+    }).unwrap();
+    pool.run_until(async {
+        for i in 1..=ROUNDS {
+            tx.send(i);
+            ack_rx.changed().await.unwrap();
+            assert_eq!(*ack_rx.borrow(), i);
+        }
+    });
+    assert!(slots(&tx) <= 1, "{} slots after {} rounds", slots(&tx), ROUNDS);
+}
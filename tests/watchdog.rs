@@ -0,0 +1,53 @@
+#![cfg(feature = "watchdog")]
+
+use std::cell::RefCell;
+use std::thread::sleep;
+use std::time::Duration;
+use futures::executor::LocalPool;
+use futures::task::LocalSpawnExt;
+use wasm_mutex::{LongHold, Mutex};
+
+thread_local! {
+    static REPORTS: RefCell<Vec<LongHold>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(hold: &LongHold) {
+    REPORTS.with_borrow_mut(|reports| reports.push(*hold));
+}
+
+#[test]
+fn long_holds_are_reported_once_with_their_site() {
+    let mut pool = LocalPool::new();
+    let mutex = Mutex::builder()
+        .name("session_state")
+        .warn_after(Duration::from_millis(1))
+        .on_long_hold(record)
+        .build(());
+
+    drop(mutex.try_lock().unwrap());
+    assert!(REPORTS.with_borrow(Vec::is_empty));
+
+    let (held, line) = (mutex.try_lock().unwrap(), line!());
+    sleep(Duration::from_millis(5));
+    let waiter = mutex.clone();
+    pool.spawner().spawn_local(async move {
+        drop(waiter.lock().await);
+    }).unwrap();
+    pool.run_until_stalled();
+    drop(held);
+    pool.run();
+
+    let reports = REPORTS.with_borrow(Vec::clone);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].name, Some("session_state"));
+    assert_eq!((reports[0].site.file(), reports[0].site.line()), (file!(), line));
+    assert!(!reports[0].released);
+    assert!(reports[0].held >= Duration::from_millis(5));
+}
+
+#[test]
+fn holds_under_the_threshold_are_not_reported() {
+    let mutex = Mutex::builder().warn_after(Duration::from_secs(60)).on_long_hold(record).build(());
+    drop(mutex.try_lock().unwrap());
+    assert!(REPORTS.with_borrow(Vec::is_empty));
+}